@@ -2,63 +2,110 @@
 
 mod timer;
 
-use super::{op, RawEvent, DebouncedEvent};
+use super::{op, EventHandler, RawEvent, DebouncedEvent};
 
 use self::timer::WatchTimer;
 
-use std::sync::mpsc;
 use std::path::PathBuf;
 use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 pub type OperationsBuffer = Arc<Mutex<HashMap<PathBuf,
                                               (Option<op::Op>, Option<PathBuf>, Option<u64>)>>>;
 
+/// Shared handle to the handler receiving debounced events.
+///
+/// Debounced events are emitted both from the backend's thread and from the timer thread, so the
+/// handler has to be shared between them.
+#[derive(Clone)]
+pub struct DebouncedTx(Arc<Mutex<Box<dyn EventHandler<DebouncedEvent>>>>);
+
+impl DebouncedTx {
+    pub fn new<F: EventHandler<DebouncedEvent>>(handler: F) -> DebouncedTx {
+        DebouncedTx(Arc::new(Mutex::new(Box::new(handler))))
+    }
+
+    /// Deliver `event` to the handler.
+    ///
+    /// The handler runs with the lock held, a panic in the handler is caught before it poisons the
+    /// lock, see `deliver`.
+    pub fn send(&self, event: DebouncedEvent) {
+        let mut handler = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        deliver(&mut **handler, event);
+    }
+}
+
+/// Call `handler` with `event`, catching a panic in the handler
+///
+/// Handlers run on the watcher's threads, a panic would stop the event loop and every later call
+/// to the watcher would fail. The panic is still printed by the panic hook.
+fn deliver<T: 'static>(handler: &mut dyn EventHandler<T>, event: T) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| handler.handle_event(event)));
+}
+
 pub enum EventTx {
-    Raw { tx: mpsc::Sender<RawEvent> },
-    Debounced {
-        tx: mpsc::Sender<DebouncedEvent>,
-        debounce: Debounce,
-    },
-    DebouncedTx { tx: mpsc::Sender<DebouncedEvent> },
+    Raw { tx: Box<dyn EventHandler<RawEvent>> },
+    Debounced { tx: DebouncedTx, debounce: Debounce },
 }
 
 impl EventTx {
+    pub fn raw<F: EventHandler<RawEvent>>(handler: F) -> EventTx {
+        EventTx::Raw { tx: Box::new(handler) }
+    }
+
+    pub fn debounced<F: EventHandler<DebouncedEvent>>(handler: F, delay: Duration) -> EventTx {
+        EventTx::debounced_named(handler, delay, None)
+    }
+
+    /// Like `debounced`, naming the timer thread `thread_name`.
+    pub fn debounced_named<F: EventHandler<DebouncedEvent>>(handler: F,
+                                                            delay: Duration,
+                                                            thread_name: Option<String>)
+                                                            -> EventTx {
+        let tx = DebouncedTx::new(handler);
+        EventTx::Debounced {
+            tx: tx.clone(),
+            debounce: Debounce::new(delay, tx, thread_name),
+        }
+    }
+
+    /// Change the delay of the debouncer. Returns `false` for raw event delivery.
+    pub fn set_delay(&mut self, delay: Duration) -> bool {
+        match *self {
+            EventTx::Raw { .. } => false,
+            EventTx::Debounced { ref mut debounce, .. } => {
+                debounce.set_delay(delay);
+                true
+            }
+        }
+    }
+
     pub fn send(&mut self, event: RawEvent) {
         match *self {
-            EventTx::Raw { ref tx } => {
-                let _ = tx.send(event);
+            EventTx::Raw { ref mut tx } => {
+                deliver(&mut **tx, event);
             }
             EventTx::Debounced { ref tx, ref mut debounce } => {
                 match (event.path, event.op, event.cookie) {
                     (None, Ok(op::RESCAN), None) => {
-                        let _ = tx.send(DebouncedEvent::Rescan);
+                        tx.send(DebouncedEvent::Rescan);
                     }
-                    (Some(path), Ok(op), cookie) => {
-                        debounce.event(path, op, cookie);
-                    }
-                    (None, Ok(_op), _cookie) => {
-                        // TODO panic!("path is None: {:?} ({:?})", _op, _cookie);
+                    (Some(path), Ok(op::UNMOUNT), None) => {
+                        tx.send(DebouncedEvent::Unmount(path));
                     }
-                    (path, Err(e), _) => {
-                        let _ = tx.send(DebouncedEvent::Error(e, path));
+                    (Some(path), Ok(op::WATCH_DROPPED), None) => {
+                        tx.send(DebouncedEvent::WatchDropped(path));
                     }
-                }
-            }
-            EventTx::DebouncedTx { ref tx } => {
-                match (event.path, event.op, event.cookie) {
-                    (None, Ok(op::RESCAN), None) => {
-                        let _ = tx.send(DebouncedEvent::Rescan);
-                    }
-                    (Some(_path), Ok(_op), _cookie) => {
-                        // TODO debounce.event(_path, _op, _cookie);
+                    (Some(path), Ok(op), cookie) => {
+                        debounce.event(path, op, cookie);
                     }
                     (None, Ok(_op), _cookie) => {
                         // TODO panic!("path is None: {:?} ({:?})", _op, _cookie);
                     }
                     (path, Err(e), _) => {
-                        let _ = tx.send(DebouncedEvent::Error(e, path));
+                        tx.send(DebouncedEvent::Error(e, path));
                     }
                 }
             }
@@ -67,7 +114,7 @@ impl EventTx {
 }
 
 pub struct Debounce {
-    tx: mpsc::Sender<DebouncedEvent>,
+    tx: DebouncedTx,
     operations_buffer: OperationsBuffer,
     rename_path: Option<PathBuf>,
     rename_cookie: Option<u32>,
@@ -75,11 +122,11 @@ pub struct Debounce {
 }
 
 impl Debounce {
-    pub fn new(delay: Duration, tx: mpsc::Sender<DebouncedEvent>) -> Debounce {
+    pub fn new(delay: Duration, tx: DebouncedTx, thread_name: Option<String>) -> Debounce {
         let operations_buffer: OperationsBuffer = Arc::new(Mutex::new(HashMap::new()));
 
         // spawns new thread
-        let timer = WatchTimer::new(tx.clone(), operations_buffer.clone(), delay);
+        let timer = WatchTimer::new(tx.clone(), operations_buffer.clone(), delay, thread_name);
 
         Debounce {
             tx: tx,
@@ -90,6 +137,10 @@ impl Debounce {
         }
     }
 
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.timer.set_delay(delay);
+    }
+
     fn check_partial_rename(&mut self, path: PathBuf, op: op::Op, cookie: Option<u32>) {
         if let Ok(mut op_buf) = self.operations_buffer.lock() {
             // the previous event was a rename event, but this one isn't; something went wrong
@@ -139,7 +190,7 @@ impl Debounce {
                             Some(op::WRITE) | // change to remove event
                             Some(op::CHMOD) => { // change to remove event
                                 *operation = Some(op::REMOVE);
-                                let _ = self.tx.send(DebouncedEvent::NoticeRemove(path.clone()));
+                                self.tx.send(DebouncedEvent::NoticeRemove(path.clone()));
                                 restart_timer(timer_id, path, &mut self.timer);
                             }
                             Some(op::RENAME) => {
@@ -174,8 +225,18 @@ impl Debounce {
     }
 
     pub fn event(&mut self, path: PathBuf, mut op: op::Op, cookie: Option<u32>) {
+        if op.contains(op::WRITE) && path.is_dir() {
+            // a directory is written along with its entries, which are reported by themselves,
+            // inotify doesn't report these writes at all while the `PollWatcher` and other
+            // backends do
+            op.remove(op::WRITE);
+            if op.is_empty() {
+                return;
+            }
+        }
+
         if op.contains(op::RESCAN) {
-            let _ = self.tx.send(DebouncedEvent::Rescan);
+            self.tx.send(DebouncedEvent::Rescan);
         }
 
         if self.rename_path.is_some() {
@@ -251,7 +312,7 @@ impl Debounce {
                     // operations_buffer entry didn't exist
                     None => {
                         *operation = Some(op::WRITE);
-                        let _ = self.tx.send(DebouncedEvent::NoticeWrite(path.clone()));
+                        self.tx.send(DebouncedEvent::NoticeWrite(path.clone()));
                         restart_timer(timer_id, path.clone(), &mut self.timer);
                     }
 
@@ -374,14 +435,14 @@ impl Debounce {
 
                         // keep chmod event
                         Some(op::CHMOD) => {
-                            let _ = self.tx.send(DebouncedEvent::NoticeRemove(path.clone()));
+                            self.tx.send(DebouncedEvent::NoticeRemove(path.clone()));
                             restart_timer(timer_id, path.clone(), &mut self.timer);
                         }
 
                         // operations_buffer entry didn't exist
                         None => {
                             *operation = Some(op::RENAME);
-                            let _ = self.tx.send(DebouncedEvent::NoticeRemove(path.clone()));
+                            self.tx.send(DebouncedEvent::NoticeRemove(path.clone()));
                             restart_timer(timer_id, path.clone(), &mut self.timer);
                         }
 
@@ -442,7 +503,7 @@ impl Debounce {
                             // operations_buffer entry didn't exist
                             None => {
                                 *operation = Some(op::REMOVE);
-                                let _ = self.tx.send(DebouncedEvent::NoticeRemove(path.clone()));
+                                self.tx.send(DebouncedEvent::NoticeRemove(path.clone()));
                                 restart_timer(timer_id, path.clone(), &mut self.timer);
                             }
 
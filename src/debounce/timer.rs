@@ -3,12 +3,11 @@ use super::super::{op, DebouncedEvent};
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
-use std::sync::{Arc, Condvar, Mutex};
 use std::collections::{BinaryHeap, HashSet};
 use std::path::PathBuf;
 use std::cmp::Ordering;
 
-use debounce::OperationsBuffer;
+use debounce::{DebouncedTx, OperationsBuffer};
 
 enum Action {
     Schedule(ScheduledEvent),
@@ -35,22 +34,19 @@ impl PartialOrd for ScheduledEvent {
 }
 
 struct ScheduleWorker {
-    trigger: Arc<Condvar>,
     request_source: mpsc::Receiver<Action>,
     schedule: BinaryHeap<ScheduledEvent>,
     ignore: HashSet<u64>,
-    tx: mpsc::Sender<DebouncedEvent>,
+    tx: DebouncedTx,
     operations_buffer: OperationsBuffer,
 }
 
 impl ScheduleWorker {
-    fn new(trigger: Arc<Condvar>,
-           request_source: mpsc::Receiver<Action>,
-           tx: mpsc::Sender<DebouncedEvent>,
+    fn new(request_source: mpsc::Receiver<Action>,
+           tx: DebouncedTx,
            operations_buffer: OperationsBuffer)
            -> ScheduleWorker {
         ScheduleWorker {
-            trigger: trigger,
             request_source: request_source,
             schedule: BinaryHeap::new(),
             ignore: HashSet::new(),
@@ -59,16 +55,14 @@ impl ScheduleWorker {
         }
     }
 
-    fn drain_request_queue(&mut self) {
-        while let Ok(action) = self.request_source.try_recv() {
-            match action {
-                Action::Schedule(event) => self.schedule.push(event),
-                Action::Ignore(ignore_id) => {
-                    for &ScheduledEvent { ref id, .. } in &self.schedule {
-                        if *id == ignore_id {
-                            self.ignore.insert(ignore_id);
-                            break;
-                        }
+    fn handle_action(&mut self, action: Action) {
+        match action {
+            Action::Schedule(event) => self.schedule.push(event),
+            Action::Ignore(ignore_id) => {
+                for &ScheduledEvent { ref id, .. } in &self.schedule {
+                    if *id == ignore_id {
+                        self.ignore.insert(ignore_id);
+                        break;
                     }
                 }
             }
@@ -90,7 +84,7 @@ impl ScheduleWorker {
                     if let Some((op, from_path, _)) = op_buf.remove(&path) {
                         let is_partial_rename = from_path.is_none();
                         if let Some(from_path) = from_path {
-                            self.tx.send(DebouncedEvent::Rename(from_path, path.clone())).unwrap();
+                            self.tx.send(DebouncedEvent::Rename(from_path, path.clone()));
                         }
                         let message = match op {
                             Some(op::CREATE) => Some(DebouncedEvent::Create(path)),
@@ -107,7 +101,7 @@ impl ScheduleWorker {
                             _ => None,
                         };
                         if let Some(m) = message {
-                            let _ = self.tx.send(m);
+                            self.tx.send(m);
                         }
                     } else {
                         // TODO error!("path not found in operations_buffer: {}", path.display())
@@ -128,29 +122,35 @@ impl ScheduleWorker {
         })
     }
 
+    /// Fire the scheduled events when they are due
+    ///
+    /// Returns once the `WatchTimer` was dropped and the remaining events were fired, so the
+    /// handler isn't kept alive by this thread.
     fn run(&mut self) {
-        let m = Mutex::new(());
-
-        // Unwrapping is safe because the mutex can't be poisoned,
-        // since we just created it.
-        let mut g = m.lock().unwrap();
-
         loop {
-            self.drain_request_queue();
-
             while self.has_event_now() {
                 self.fire_event();
             }
 
-            let wait_duration = self.duration_until_next_event();
-
-            // Unwrapping is safe because the mutex can't be poisoned,
-            // since we haven't shared it with another thread.
-            g = if let Some(wait_duration) = wait_duration {
-                self.trigger.wait_timeout(g, wait_duration).unwrap().0
-            } else {
-                self.trigger.wait(g).unwrap()
+            let action = match self.duration_until_next_event() {
+                Some(wait_duration) => {
+                    match self.request_source.recv_timeout(wait_duration) {
+                        Ok(action) => action,
+                        Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                        Err(mpsc::RecvTimeoutError::Disconnected) => {
+                            thread::sleep(wait_duration);
+                            continue;
+                        }
+                    }
+                }
+                None => {
+                    match self.request_source.recv() {
+                        Ok(action) => action,
+                        Err(_) => return,
+                    }
+                }
             };
+            self.handle_action(action);
         }
     }
 }
@@ -158,27 +158,29 @@ impl ScheduleWorker {
 pub struct WatchTimer {
     counter: u64,
     schedule_tx: mpsc::Sender<Action>,
-    trigger: Arc<Condvar>,
     delay: Duration,
 }
 
 impl WatchTimer {
-    pub fn new(tx: mpsc::Sender<DebouncedEvent>,
+    pub fn new(tx: DebouncedTx,
                operations_buffer: OperationsBuffer,
-               delay: Duration)
+               delay: Duration,
+               thread_name: Option<String>)
                -> WatchTimer {
         let (schedule_tx, schedule_rx) = mpsc::channel();
-        let trigger = Arc::new(Condvar::new());
 
-        let trigger_worker = trigger.clone();
-        thread::spawn(move || {
-            ScheduleWorker::new(trigger_worker, schedule_rx, tx, operations_buffer).run();
-        });
+        let mut thread = thread::Builder::new();
+        if let Some(name) = thread_name {
+            thread = thread.name(name);
+        }
+        thread.spawn(move || {
+                ScheduleWorker::new(schedule_rx, tx, operations_buffer).run();
+            })
+            .expect("failed to spawn the debounce timer thread");
 
         WatchTimer {
             counter: 0,
             schedule_tx: schedule_tx,
-            trigger: trigger,
             delay: delay,
         }
     }
@@ -194,11 +196,13 @@ impl WatchTimer {
             }))
             .expect("Failed to send a request to the global scheduling worker");
 
-        self.trigger.notify_one();
-
         self.counter
     }
 
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
     pub fn ignore(&self, id: u64) {
         self.schedule_tx
             .send(Action::Ignore(id))
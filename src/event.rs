@@ -0,0 +1,229 @@
+//! Structured description of the operation behind an event
+//!
+//! [`EventKind`](enum.EventKind.html) is a hierarchy: the top level says what happened to a path
+//! (it was created, modified, removed or accessed) and the lower levels say more about it where
+//! the backend can tell. A backend that can't tell uses the `Any` variant of the level it stopped
+//! at, so matching on the top level is always enough to interpret an event.
+//!
+//! Every `EventKind` maps to the [`Op`](../op/struct.Op.html) bitflags through `From`, which is
+//! how the `op` field of [`RawEvent`](../struct.RawEvent.html) is derived on backends reporting a
+//! kind:
+//!
+//! | `EventKind`                  | `Op`            |
+//! |------------------------------|-----------------|
+//! | `Create(_)`                  | `CREATE`        |
+//! | `Modify(Data(_))`            | `WRITE`         |
+//! | `Modify(Metadata(_))`        | `CHMOD`         |
+//! | `Modify(Name(_))`            | `RENAME`        |
+//! | `Remove(_)`                  | `REMOVE`        |
+//! | `Access(Read)`               | `ACCESS`        |
+//! | `Access(Open(_))`            | `OPEN`          |
+//! | `Access(Close(Write))`       | `CLOSE_WRITE`   |
+//! | `Access(Close(Read))`        | `CLOSE_NOWRITE` |
+//! | anything else                | empty           |
+
+use super::op::{self, Op};
+
+/// What happened to a path
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// The kind of the event is unknown
+    Any,
+
+    /// A file or directory was read, opened or closed, without being changed
+    Access(AccessKind),
+
+    /// A file or directory was created
+    Create(CreateKind),
+
+    /// The data, metadata or name of a file or directory was changed
+    Modify(ModifyKind),
+
+    /// A file or directory was removed
+    Remove(RemoveKind),
+
+    /// An event that doesn't fit any other kind
+    Other,
+}
+
+/// How a path was accessed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AccessKind {
+    /// The kind of access is unknown
+    Any,
+
+    /// A file was read
+    Read,
+
+    /// A file or directory was opened
+    Open(AccessMode),
+
+    /// A file or directory was closed
+    Close(AccessMode),
+
+    /// An access that doesn't fit any other kind
+    Other,
+}
+
+/// Mode a path was opened or closed with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AccessMode {
+    /// The mode is unknown
+    Any,
+
+    /// The path was opened for reading only
+    Read,
+
+    /// The path was opened for writing
+    Write,
+
+    /// A mode that doesn't fit any other kind
+    Other,
+}
+
+/// What was created
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CreateKind {
+    /// It is unknown what was created
+    Any,
+
+    /// A file was created
+    File,
+
+    /// A directory was created
+    Folder,
+
+    /// Something else was created, for example a device node
+    Other,
+}
+
+/// What was modified
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ModifyKind {
+    /// It is unknown what was modified
+    Any,
+
+    /// The data of a file was changed
+    Data(DataChange),
+
+    /// The metadata of a file or directory was changed
+    Metadata(MetadataKind),
+
+    /// A file or directory was renamed
+    Name(RenameMode),
+
+    /// A modification that doesn't fit any other kind
+    Other,
+}
+
+/// How the data of a file was changed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DataChange {
+    /// It is unknown how the data was changed
+    Any,
+
+    /// The size of the file changed
+    Size,
+
+    /// The content of the file changed
+    Content,
+
+    /// A change that doesn't fit any other kind
+    Other,
+}
+
+/// Which metadata was changed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MetadataKind {
+    /// It is unknown which metadata was changed
+    Any,
+
+    /// The access time was changed
+    AccessTime,
+
+    /// The modification time was changed
+    WriteTime,
+
+    /// The permissions were changed
+    Permissions,
+
+    /// The owner or group was changed
+    Ownership,
+
+    /// Extended attributes were changed
+    Extended,
+
+    /// A change that doesn't fit any other kind
+    Other,
+}
+
+/// Which side of a rename an event describes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenameMode {
+    /// It is unknown which side of the rename the event describes
+    Any,
+
+    /// The event holds the old path
+    From,
+
+    /// The event holds the new path
+    To,
+
+    /// The event describes the whole rename
+    Both,
+
+    /// A rename that doesn't fit any other kind
+    Other,
+}
+
+/// What was removed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RemoveKind {
+    /// It is unknown what was removed
+    Any,
+
+    /// A file was removed
+    File,
+
+    /// A directory was removed
+    Folder,
+
+    /// Something else was removed, for example a device node
+    Other,
+}
+
+impl From<EventKind> for Op {
+    fn from(kind: EventKind) -> Op {
+        match kind {
+            EventKind::Create(_) => op::CREATE,
+            EventKind::Modify(ModifyKind::Metadata(_)) => op::CHMOD,
+            EventKind::Modify(ModifyKind::Name(_)) => op::RENAME,
+            EventKind::Modify(_) => op::WRITE,
+            EventKind::Remove(_) => op::REMOVE,
+            EventKind::Access(AccessKind::Read) => op::ACCESS,
+            EventKind::Access(AccessKind::Open(_)) => op::OPEN,
+            EventKind::Access(AccessKind::Close(AccessMode::Write)) => op::CLOSE_WRITE,
+            EventKind::Access(AccessKind::Close(AccessMode::Read)) => op::CLOSE_NOWRITE,
+            EventKind::Access(_) |
+            EventKind::Any |
+            EventKind::Other => Op::empty(),
+        }
+    }
+}
+
+#[test]
+fn event_kind_to_op() {
+    assert_eq!(Op::from(EventKind::Create(CreateKind::Folder)), op::CREATE);
+    assert_eq!(Op::from(EventKind::Modify(ModifyKind::Data(DataChange::Content))),
+               op::WRITE);
+    assert_eq!(Op::from(EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any))),
+               op::CHMOD);
+    assert_eq!(Op::from(EventKind::Modify(ModifyKind::Name(RenameMode::To))), op::RENAME);
+    assert_eq!(Op::from(EventKind::Remove(RemoveKind::File)), op::REMOVE);
+    assert_eq!(Op::from(EventKind::Access(AccessKind::Close(AccessMode::Write))),
+               op::CLOSE_WRITE);
+    assert_eq!(Op::from(EventKind::Access(AccessKind::Read)), op::ACCESS);
+    assert_eq!(Op::from(EventKind::Access(AccessKind::Close(AccessMode::Read))),
+               op::CLOSE_NOWRITE);
+    assert_eq!(Op::from(EventKind::Access(AccessKind::Any)), Op::empty());
+}
@@ -0,0 +1,786 @@
+//! Watcher implementation for the fanotify Linux API
+//!
+//! fanotify marks a whole filesystem at once, so watching a large tree doesn't need one watch per
+//! directory like inotify does. Events identify the directory they happened in by a file handle
+//! and the entry by its name (`FAN_REPORT_DIR_FID` and `FAN_REPORT_NAME`), which are resolved to a
+//! path with `open_by_handle_at`, and they carry the id of the process that caused them.
+//!
+//! Marking a filesystem needs the `CAP_SYS_ADMIN` capability and resolving file handles needs
+//! `CAP_DAC_READ_SEARCH`. Reporting names needs Linux 5.9, before Linux 5.17 the two halves of a
+//! rename are paired from separate events.
+
+extern crate libc;
+
+use mio::{self, EventLoop};
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io;
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::mpsc::{self, Sender};
+use std::thread::Builder as ThreadBuilder;
+use std::time::Duration;
+use self::libc::c_int;
+use super::{Config, Error, ErrorKind, EventHandler, RawEvent, DebouncedEvent, op, Op, Result,
+            SymlinkPolicy, Watcher, WatcherBuilder, WatcherStats, WatchOptions, RecursiveMode};
+use super::event::{AccessKind, AccessMode, CreateKind, DataChange, EventKind, MetadataKind,
+                   ModifyKind, RemoveKind, RenameMode};
+use super::debounce::EventTx;
+
+const FANOTIFY: mio::Token = mio::Token(0);
+
+/// Operations every mark reports, with `FAN_RENAME` replaced by `FAN_MOVE` before Linux 5.17
+const DEFAULT_MASK: u64 = libc::FAN_CREATE | libc::FAN_DELETE | libc::FAN_RENAME |
+                          libc::FAN_MODIFY | libc::FAN_CLOSE_WRITE | libc::FAN_ATTRIB |
+                          libc::FAN_ONDIR;
+
+/// Size of the buffer events are read into
+const BUFFER_SIZE: usize = 16384;
+
+/// Identifier of a filesystem, as reported by `statfs` and in fanotify events
+type Fsid = [c_int; 2];
+
+/// Watcher implementation based on fanotify
+///
+/// A single mark covers the whole filesystem a watched path lives on, events outside of the
+/// watched paths are dropped by the watcher. Events have the `pid` of the process that caused
+/// them.
+///
+/// Creating the watcher fails with an `ErrorKind::PermissionDenied` error when the process lacks
+/// `CAP_SYS_ADMIN`, fall back to the `INotifyWatcher` in that case:
+///
+/// ```no_run
+/// # extern crate notify;
+/// use notify::{Error, ErrorKind, FanotifyWatcher, INotifyWatcher, RawEvent, Watcher};
+/// use std::sync::mpsc::channel;
+///
+/// # fn main() {
+/// let (tx, rx) = channel::<RawEvent>();
+/// let watcher: Box<dyn Watcher> = match FanotifyWatcher::new_raw(tx.clone()) {
+///     Ok(watcher) => Box::new(watcher),
+///     Err(Error { kind: ErrorKind::PermissionDenied(_), .. }) => {
+///         Box::new(INotifyWatcher::new_raw(tx).unwrap())
+///     }
+///     Err(e) => panic!("{}", e),
+/// };
+/// # let _ = (watcher, rx);
+/// # }
+/// ```
+pub struct FanotifyWatcher(mio::Sender<EventLoopMsg>);
+
+/// A marked filesystem
+struct Filesystem {
+    /// Directory on the filesystem, used to resolve file handles and to remove the mark
+    dir: File,
+    /// Number of watched paths on the filesystem
+    roots: usize,
+}
+
+/// A watched path
+struct Root {
+    /// Path events are matched against, the watched path with symbolic links resolved
+    canonical: PathBuf,
+    fsid: Fsid,
+    options: WatchOptions,
+}
+
+struct FanotifyHandler {
+    fd: RawFd,
+    event_tx: EventTx,
+    roots: HashMap<PathBuf, Root>,
+    filesystems: HashMap<Fsid, Filesystem>,
+    mask: u64,
+    access_ops: Op,
+    next_cookie: u32,
+    /// Watched path and pid of a `FAN_MOVED_FROM` event waiting for its `FAN_MOVED_TO`, the
+    /// path is `None` if it isn't watched
+    moved_from: Option<(Option<PathBuf>, Option<u32>)>,
+    buffer: Vec<u8>,
+}
+
+enum EventLoopMsg {
+    AddWatch(PathBuf, WatchOptions, Sender<Result<()>>),
+    RemoveWatch(PathBuf, Sender<Result<()>>),
+    Configure(Config, Sender<Result<bool>>),
+    WatchedPaths(Sender<Vec<PathBuf>>),
+    Stats(Sender<WatcherStats>),
+    Shutdown,
+}
+
+/// Name and directory file handle of an event, from a `FAN_EVENT_INFO_TYPE_*DFID_NAME` record
+struct DirEntry<'a> {
+    fsid: Fsid,
+    /// `struct file_handle`, header included
+    handle: &'a [u8],
+    name: &'a [u8],
+}
+
+impl mio::Handler for FanotifyHandler {
+    type Timeout = ();
+    type Message = EventLoopMsg;
+
+    fn ready(&mut self,
+             _event_loop: &mut EventLoop<FanotifyHandler>,
+             token: mio::Token,
+             events: mio::EventSet) {
+        match token {
+            FANOTIFY => {
+                assert!(events.is_readable());
+                self.handle_events();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn notify(&mut self, event_loop: &mut EventLoop<FanotifyHandler>, msg: EventLoopMsg) {
+        match msg {
+            EventLoopMsg::AddWatch(path, options, tx) => {
+                let _ = tx.send(self.add_root(path, options));
+            }
+            EventLoopMsg::RemoveWatch(path, tx) => {
+                let _ = tx.send(self.remove_root(&path));
+            }
+            EventLoopMsg::Configure(config, tx) => {
+                let _ = tx.send(Ok(self.configure(config)));
+            }
+            EventLoopMsg::WatchedPaths(tx) => {
+                let mut paths: Vec<PathBuf> = self.roots.keys().cloned().collect();
+                paths.sort();
+                let _ = tx.send(paths);
+            }
+            EventLoopMsg::Stats(tx) => {
+                let _ = tx.send(WatcherStats {
+                    roots: self.roots.len(),
+                    descriptors: self.filesystems.len(),
+                    polled_files: 0,
+                    max_user_watches: None,
+                });
+            }
+            EventLoopMsg::Shutdown => {
+                event_loop.shutdown();
+            }
+        }
+    }
+}
+
+impl Drop for FanotifyHandler {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Read a `T` at `offset` of `buffer`, which doesn't need to be aligned
+fn read_at<T: Copy>(buffer: &[u8], offset: usize) -> T {
+    assert!(offset + mem::size_of::<T>() <= buffer.len());
+    unsafe { ptr::read_unaligned(buffer[offset..].as_ptr() as *const T) }
+}
+
+/// Parse a `struct fanotify_event_info_fid` record followed by the name of the entry
+fn parse_dir_entry<'a>(record: &'a [u8]) -> Option<DirEntry<'a>> {
+    // header, fsid and the two integers of `struct file_handle`
+    if record.len() < 20 {
+        return None;
+    }
+    let fsid = [read_at::<c_int>(record, 4), read_at::<c_int>(record, 8)];
+    let handle_bytes = read_at::<u32>(record, 12) as usize;
+    let name_start = 20 + handle_bytes;
+    if name_start > record.len() {
+        return None;
+    }
+    let name = &record[name_start..];
+    let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+    Some(DirEntry {
+        fsid: fsid,
+        handle: &record[12..name_start],
+        name: &name[..name_len],
+    })
+}
+
+/// Filesystem `dir` lives on
+fn fsid_of(dir: &File) -> io::Result<Fsid> {
+    unsafe {
+        let mut buf: libc::statfs = mem::zeroed();
+        if libc::fstatfs(dir.as_raw_fd(), &mut buf) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(mem::transmute::<libc::fsid_t, Fsid>(buf.f_fsid))
+    }
+}
+
+/// Kinds of the operations in `mask`, apart from renames
+fn event_kinds(mask: u64) -> Vec<EventKind> {
+    let is_dir = mask & libc::FAN_ONDIR != 0;
+    let mut kinds = Vec::new();
+    if mask & libc::FAN_CREATE != 0 {
+        kinds.push(EventKind::Create(if is_dir {
+            CreateKind::Folder
+        } else {
+            CreateKind::File
+        }));
+    }
+    if mask & libc::FAN_MODIFY != 0 {
+        kinds.push(EventKind::Modify(ModifyKind::Data(DataChange::Content)));
+    }
+    if mask & libc::FAN_ATTRIB != 0 {
+        kinds.push(EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any)));
+    }
+    if mask & libc::FAN_ACCESS != 0 {
+        kinds.push(EventKind::Access(AccessKind::Read));
+    }
+    if mask & libc::FAN_OPEN != 0 {
+        kinds.push(EventKind::Access(AccessKind::Open(AccessMode::Any)));
+    }
+    if mask & libc::FAN_CLOSE_NOWRITE != 0 {
+        kinds.push(EventKind::Access(AccessKind::Close(AccessMode::Read)));
+    }
+    if mask & libc::FAN_CLOSE_WRITE != 0 {
+        kinds.push(EventKind::Access(AccessKind::Close(AccessMode::Write)));
+    }
+    if mask & libc::FAN_DELETE != 0 {
+        kinds.push(EventKind::Remove(if is_dir {
+            RemoveKind::Folder
+        } else {
+            RemoveKind::File
+        }));
+    }
+    kinds
+}
+
+impl FanotifyHandler {
+    fn new(event_tx: EventTx, builder: &WatcherBuilder) -> Result<FanotifyHandler> {
+        let flags = libc::FAN_CLASS_NOTIF | libc::FAN_CLOEXEC | libc::FAN_NONBLOCK |
+                    libc::FAN_REPORT_DFID_NAME;
+        let fd = unsafe {
+            libc::fanotify_init(flags, (libc::O_RDONLY | libc::O_CLOEXEC) as libc::c_uint)
+        };
+        if fd == -1 {
+            let err = io::Error::last_os_error();
+            return Err(match err.raw_os_error() {
+                Some(libc::EPERM) => {
+                    Error::permission_denied("fanotify needs the CAP_SYS_ADMIN capability, use \
+                                              the INotifyWatcher instead")
+                }
+                Some(libc::EINVAL) => {
+                    Error::generic("the kernel doesn't support reporting names with fanotify, \
+                                    Linux 5.9 or later is needed")
+                }
+                Some(libc::ENOSYS) => Error::generic("the kernel doesn't support fanotify"),
+                Some(libc::EMFILE) => Error::new(ErrorKind::MaxInstances),
+                _ => Error::io(err),
+            });
+        }
+
+        // the debouncer doesn't handle the opt-in operations, so they aren't requested from
+        // fanotify
+        let access_ops = match event_tx {
+            EventTx::Raw { .. } => builder.access_ops.unwrap_or(Op::empty()),
+            EventTx::Debounced { .. } => Op::empty(),
+        };
+        let mut mask = DEFAULT_MASK;
+        if access_ops.contains(op::ACCESS) {
+            mask |= libc::FAN_ACCESS;
+        }
+        if access_ops.contains(op::OPEN) {
+            mask |= libc::FAN_OPEN;
+        }
+        if access_ops.contains(op::CLOSE_NOWRITE) {
+            mask |= libc::FAN_CLOSE_NOWRITE;
+        }
+
+        Ok(FanotifyHandler {
+            fd: fd,
+            event_tx: event_tx,
+            roots: HashMap::new(),
+            filesystems: HashMap::new(),
+            mask: mask,
+            access_ops: access_ops,
+            next_cookie: 0,
+            moved_from: None,
+            buffer: vec![0; BUFFER_SIZE],
+        })
+    }
+
+    fn configure(&mut self, config: Config) -> bool {
+        match config {
+            Config::DebounceDelay(delay) => self.event_tx.set_delay(delay),
+            Config::RenameWindow(_) |
+            Config::PollInterval(_) => false,
+        }
+    }
+
+    /// Watch `path` for the user, marking its filesystem unless it already is
+    fn add_root(&mut self, path: PathBuf, mut options: WatchOptions) -> Result<()> {
+        options.ops.insert(self.access_ops);
+        if let EventTx::Debounced { .. } = self.event_tx {
+            // the debouncer doesn't handle the opt-in operations
+            options.ops.remove(op::ACCESS | op::OPEN | op::CLOSE_NOWRITE);
+        }
+        let canonical = try!(fs::canonicalize(&path)
+            .map_err(|e| Error::io(e).add_path(path.clone())));
+        let dir_path = if canonical.is_dir() {
+            canonical.as_path()
+        } else {
+            canonical.parent().unwrap_or(&canonical)
+        };
+        let dir = try!(File::open(dir_path).map_err(|e| Error::io(e).add_path(path.clone())));
+        let fsid = try!(fsid_of(&dir).map_err(|e| Error::io(e).add_path(path.clone())));
+
+        if let Some(old) = self.roots.remove(&path) {
+            self.release_filesystem(old.fsid);
+        }
+        if !self.filesystems.contains_key(&fsid) {
+            try!(self.mark(&dir, libc::FAN_MARK_ADD).map_err(|e| e.add_path(path.clone())));
+            self.filesystems.insert(fsid,
+                                    Filesystem {
+                                        dir: dir,
+                                        roots: 0,
+                                    });
+        }
+        self.filesystems.get_mut(&fsid).unwrap().roots += 1;
+        self.roots.insert(path,
+                          Root {
+                              canonical: canonical,
+                              fsid: fsid,
+                              options: options,
+                          });
+        Ok(())
+    }
+
+    fn remove_root(&mut self, path: &Path) -> Result<()> {
+        match self.roots.remove(path) {
+            Some(root) => {
+                self.release_filesystem(root.fsid);
+                Ok(())
+            }
+            None => Err(Error::watch_not_found().add_path(path.to_owned())),
+        }
+    }
+
+    /// Remove the mark of the filesystem `fsid` once no watched path is left on it
+    fn release_filesystem(&mut self, fsid: Fsid) {
+        let unused = match self.filesystems.get_mut(&fsid) {
+            Some(filesystem) => {
+                filesystem.roots -= 1;
+                filesystem.roots == 0
+            }
+            None => false,
+        };
+        if unused {
+            let filesystem = self.filesystems.remove(&fsid).unwrap();
+            let _ = self.mark(&filesystem.dir, libc::FAN_MARK_REMOVE);
+        }
+    }
+
+    /// Add or remove the mark of the filesystem `dir` lives on
+    ///
+    /// Kernels before Linux 5.17 don't know `FAN_RENAME`, the two halves of renames are paired
+    /// from `FAN_MOVED_FROM` and `FAN_MOVED_TO` events instead.
+    fn mark(&mut self, dir: &File, action: libc::c_uint) -> Result<()> {
+        let mut res = self.fanotify_mark(dir, action);
+        if action == libc::FAN_MARK_ADD && self.mask & libc::FAN_RENAME != 0 &&
+           res.as_ref().err().and_then(|e| e.raw_os_error()) == Some(libc::EINVAL) {
+            self.mask = self.mask & !libc::FAN_RENAME | libc::FAN_MOVE;
+            res = self.fanotify_mark(dir, action);
+        }
+        res.map_err(|err| match err.raw_os_error() {
+            Some(libc::EPERM) => {
+                Error::permission_denied("marking a filesystem with fanotify needs the \
+                                          CAP_SYS_ADMIN capability")
+            }
+            Some(libc::EINVAL) if action == libc::FAN_MARK_ADD => {
+                Error::generic("the kernel doesn't support marking filesystems with fanotify")
+            }
+            _ => Error::io(err),
+        })
+    }
+
+    fn fanotify_mark(&self, dir: &File, action: libc::c_uint) -> io::Result<()> {
+        let res = unsafe {
+            libc::fanotify_mark(self.fd,
+                                action | libc::FAN_MARK_FILESYSTEM,
+                                self.mask,
+                                dir.as_raw_fd(),
+                                ptr::null())
+        };
+        if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read and handle the available events
+    fn handle_events(&mut self) {
+        loop {
+            let len = unsafe {
+                libc::read(self.fd,
+                           self.buffer.as_mut_ptr() as *mut libc::c_void,
+                           self.buffer.len())
+            };
+            if len == -1 {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::WouldBlock => {
+                        // the other half of a rename is queued right after the first one
+                        if let Some((from, pid)) = self.moved_from.take() {
+                            self.report_rename(from, None, false, pid);
+                        }
+                    }
+                    io::ErrorKind::Interrupted => continue,
+                    _ => {
+                        self.event_tx.send(RawEvent {
+                                               path: None,
+                                               op: Err(Error::io(err)),
+                                               kind: None,
+                                               cookie: None,
+                                               pid: None,
+                                           });
+                    }
+                }
+                return;
+            }
+            if len == 0 {
+                return;
+            }
+
+            let buffer = mem::replace(&mut self.buffer, Vec::new());
+            let mut offset = 0;
+            while offset + mem::size_of::<libc::fanotify_event_metadata>() <= len as usize {
+                let meta = read_at::<libc::fanotify_event_metadata>(&buffer, offset);
+                let event_len = meta.event_len as usize;
+                if event_len < meta.metadata_len as usize || offset + event_len > len as usize {
+                    break;
+                }
+                if meta.vers == libc::FANOTIFY_METADATA_VERSION {
+                    let event = &buffer[offset..offset + event_len];
+                    self.handle_event(&meta, &event[meta.metadata_len as usize..]);
+                }
+                if meta.fd >= 0 {
+                    unsafe {
+                        libc::close(meta.fd);
+                    }
+                }
+                offset += event_len;
+            }
+            self.buffer = buffer;
+        }
+    }
+
+    /// Handle a single event, `info` holds its information records
+    fn handle_event(&mut self, meta: &libc::fanotify_event_metadata, info: &[u8]) {
+        if meta.mask & libc::FAN_Q_OVERFLOW != 0 {
+            self.event_tx.send(RawEvent {
+                                   path: None,
+                                   op: Ok(op::RESCAN),
+                                   kind: None,
+                                   cookie: None,
+                                   pid: None,
+                               });
+            return;
+        }
+
+        let mut entry = None;
+        let mut old_entry = None;
+        let mut new_entry = None;
+        let mut offset = 0;
+        while offset + 4 <= info.len() {
+            let info_type = info[offset];
+            let record_len = read_at::<u16>(info, offset + 2) as usize;
+            if record_len == 0 || offset + record_len > info.len() {
+                break;
+            }
+            let record = &info[offset..offset + record_len];
+            match info_type {
+                libc::FAN_EVENT_INFO_TYPE_DFID_NAME => entry = parse_dir_entry(record),
+                libc::FAN_EVENT_INFO_TYPE_OLD_DFID_NAME => old_entry = parse_dir_entry(record),
+                libc::FAN_EVENT_INFO_TYPE_NEW_DFID_NAME => new_entry = parse_dir_entry(record),
+                _ => {}
+            }
+            offset += record_len;
+        }
+
+        let pid = Some(meta.pid as u32);
+        let is_dir = meta.mask & libc::FAN_ONDIR != 0;
+        if meta.mask & libc::FAN_RENAME != 0 {
+            let from = old_entry.and_then(|e| self.resolve(&e))
+                .and_then(|path| self.watched_path(&path, op::RENAME));
+            let to = new_entry.and_then(|e| self.resolve(&e))
+                .and_then(|path| self.watched_path(&path, op::RENAME));
+            self.report_rename(from, to, is_dir, pid);
+        }
+
+        let path = entry.and_then(|e| self.resolve(&e));
+        if meta.mask & libc::FAN_MOVED_TO != 0 {
+            let to = path.as_ref().and_then(|path| self.watched_path(path, op::RENAME));
+            let (from, from_pid) = self.moved_from.take().unwrap_or((None, pid));
+            self.report_rename(from, to, is_dir, from_pid);
+        }
+        if let Some(ref path) = path {
+            for kind in event_kinds(meta.mask) {
+                if let Some(path) = self.watched_path(path, kind.into()) {
+                    self.send(path, kind, None, pid);
+                }
+            }
+        }
+        if meta.mask & libc::FAN_MOVED_FROM != 0 {
+            if let Some((from, pid)) = self.moved_from.take() {
+                self.report_rename(from, None, false, pid);
+            }
+            let from = path.as_ref().and_then(|path| self.watched_path(path, op::RENAME));
+            self.moved_from = Some((from, pid));
+        }
+    }
+
+    /// Report a rename from the watched path `from` to the watched path `to`, as a remove or a
+    /// create if only one of them is watched
+    fn report_rename(&mut self,
+                     from: Option<PathBuf>,
+                     to: Option<PathBuf>,
+                     is_dir: bool,
+                     pid: Option<u32>) {
+        match (from, to) {
+            (Some(from), Some(to)) => {
+                self.next_cookie = self.next_cookie.wrapping_add(1);
+                let cookie = Some(self.next_cookie);
+                self.send(from, EventKind::Modify(ModifyKind::Name(RenameMode::From)), cookie, pid);
+                self.send(to, EventKind::Modify(ModifyKind::Name(RenameMode::To)), cookie, pid);
+            }
+            // moved out of the watched paths
+            (Some(from), None) => {
+                self.send(from, EventKind::Remove(RemoveKind::Any), None, pid);
+            }
+            // moved into the watched paths
+            (None, Some(to)) => {
+                let kind = EventKind::Create(if is_dir {
+                    CreateKind::Folder
+                } else {
+                    CreateKind::File
+                });
+                self.send(to, kind, None, pid);
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn send(&mut self, path: PathBuf, kind: EventKind, cookie: Option<u32>, pid: Option<u32>) {
+        self.event_tx.send(RawEvent {
+                               path: Some(path),
+                               op: Ok(kind.into()),
+                               kind: Some(kind),
+                               cookie: cookie,
+                               pid: pid,
+                           });
+    }
+
+    /// Path of the entry of an event, `None` if its directory is gone
+    fn resolve(&self, entry: &DirEntry) -> Option<PathBuf> {
+        let dir = match self.filesystems.get(&entry.fsid) {
+            Some(filesystem) => &filesystem.dir,
+            None => return None,
+        };
+
+        // copy the handle to a buffer aligned for `struct file_handle`
+        let mut handle = vec![0u32; (entry.handle.len() + 3) / 4];
+        unsafe {
+            ptr::copy_nonoverlapping(entry.handle.as_ptr(),
+                                     handle.as_mut_ptr() as *mut u8,
+                                     entry.handle.len());
+        }
+        let fd = unsafe {
+            libc::open_by_handle_at(dir.as_raw_fd(),
+                                    handle.as_mut_ptr() as *mut libc::file_handle,
+                                    libc::O_PATH | libc::O_CLOEXEC)
+        };
+        if fd == -1 {
+            return None;
+        }
+        let link = fs::read_link(format!("/proc/self/fd/{}", fd));
+        unsafe {
+            libc::close(fd);
+        }
+        let path = match link {
+            Ok(path) => path,
+            Err(_) => return None,
+        };
+        if path.as_os_str().as_bytes().ends_with(b" (deleted)") {
+            return None;
+        }
+        if entry.name.is_empty() || entry.name == b"." {
+            Some(path)
+        } else {
+            Some(path.join(OsStr::from_bytes(entry.name)))
+        }
+    }
+
+    /// Path of `canonical` below the watched path it belongs to, if `ops` are reported for it
+    fn watched_path(&self, canonical: &Path, ops: Op) -> Option<PathBuf> {
+        let found = self.roots
+            .iter()
+            .filter(|&(_, root)| {
+                canonical.starts_with(&root.canonical) &&
+                (root.options.recursive_mode.is_recursive() || canonical == root.canonical ||
+                 canonical.parent() == Some(&root.canonical))
+            })
+            .max_by_key(|&(_, root)| root.canonical.components().count());
+        match found {
+            Some((path, root)) => {
+                let relative = canonical.strip_prefix(&root.canonical).unwrap();
+                if root.options.ops.intersects(ops) && root.options.filter.matches(relative) {
+                    if relative.as_os_str().is_empty() {
+                        Some(path.clone())
+                    } else {
+                        Some(path.join(relative))
+                    }
+                } else {
+                    None
+                }
+            }
+            None => None,
+        }
+    }
+}
+
+/// Returns `path` relative to the current directory, unless it's absolute
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_owned())
+    } else {
+        let p = try!(env::current_dir().map_err(Error::io));
+        Ok(p.join(path))
+    }
+}
+
+impl FanotifyWatcher {
+    fn from_event_tx(event_tx: EventTx, builder: &WatcherBuilder) -> Result<FanotifyWatcher> {
+        let handler = try!(FanotifyHandler::new(event_tx, builder));
+
+        EventLoop::new()
+            .and_then(|mut event_loop| {
+                let fanotify_fd = handler.fd;
+                let evented_fanotify = mio::unix::EventedFd(&fanotify_fd);
+
+                event_loop.register(&evented_fanotify,
+                                    FANOTIFY,
+                                    mio::EventSet::readable(),
+                                    mio::PollOpt::level())
+                    .map(|_| (event_loop, handler))
+            })
+            .map(|(mut event_loop, mut handler)| {
+                let channel = event_loop.channel();
+
+                let thread_name = builder.thread_name
+                    .clone()
+                    .unwrap_or_else(|| "Fanotify Watcher".to_owned());
+
+                ThreadBuilder::new()
+                    .name(thread_name)
+                    .spawn(move || event_loop.run(&mut handler))
+                    .unwrap();
+
+                FanotifyWatcher(channel)
+            })
+            .map_err(Error::io)
+    }
+}
+
+impl Watcher for FanotifyWatcher {
+    fn new_raw<F: EventHandler<RawEvent>>(handler: F) -> Result<FanotifyWatcher> {
+        FanotifyWatcher::from_event_tx(EventTx::raw(handler), &WatcherBuilder::new())
+    }
+
+    fn new<F: EventHandler<DebouncedEvent>>(handler: F,
+                                            delay: Duration)
+                                            -> Result<FanotifyWatcher> {
+        FanotifyWatcher::from_event_tx(EventTx::debounced(handler, delay), &WatcherBuilder::new())
+    }
+
+    fn from_builder_raw<F: EventHandler<RawEvent>>(handler: F,
+                                                   builder: &WatcherBuilder)
+                                                   -> Result<FanotifyWatcher> {
+        FanotifyWatcher::from_event_tx(EventTx::raw(handler), builder)
+    }
+
+    fn from_builder<F: EventHandler<DebouncedEvent>>(handler: F,
+                                                     builder: &WatcherBuilder)
+                                                     -> Result<FanotifyWatcher> {
+        FanotifyWatcher::from_event_tx(try!(builder.debounced_tx(handler)), builder)
+    }
+
+    fn watch(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()> {
+        self.watch_with(path, WatchOptions::new(recursive_mode))
+    }
+
+    /// Path filters and operation masks are supported, following a path, symbolic link policies
+    /// and one-shot watches aren't.
+    fn watch_with(&mut self, path: &Path, options: WatchOptions) -> Result<()> {
+        if options.follow_path {
+            return Err(Error::invalid_config("following a path is not supported by this watcher")
+                .add_path(path.to_owned()));
+        }
+        if options.symlinks != SymlinkPolicy::Follow {
+            return Err(Error::invalid_config("symbolic link policies are not supported by this \
+                                              watcher")
+                .add_path(path.to_owned()));
+        }
+        if options.once {
+            return Err(Error::invalid_config("one-shot watches are not supported by this watcher")
+                .add_path(path.to_owned()));
+        }
+        let pb = try!(absolute_path(path));
+        let (tx, rx) = mpsc::channel();
+        let msg = EventLoopMsg::AddWatch(pb, options, tx);
+
+        // we expect the event loop to live and reply => unwraps must not panic
+        self.0.send(msg).unwrap();
+        rx.recv().unwrap()
+    }
+
+    fn unwatch(&mut self, path: &Path) -> Result<()> {
+        let pb = try!(absolute_path(path));
+        let (tx, rx) = mpsc::channel();
+        let msg = EventLoopMsg::RemoveWatch(pb, tx);
+
+        // we expect the event loop to live and reply => unwraps must not panic
+        self.0.send(msg).unwrap();
+        rx.recv().unwrap()
+    }
+
+    fn configure(&mut self, option: Config) -> Result<bool> {
+        let (tx, rx) = mpsc::channel();
+        let msg = EventLoopMsg::Configure(option, tx);
+
+        // we expect the event loop to live and reply => unwraps must not panic
+        self.0.send(msg).unwrap();
+        rx.recv().unwrap()
+    }
+
+    fn watched_paths(&self) -> Result<Vec<PathBuf>> {
+        let (tx, rx) = mpsc::channel();
+
+        // we expect the event loop to live and reply => unwraps must not panic
+        self.0.send(EventLoopMsg::WatchedPaths(tx)).unwrap();
+        Ok(rx.recv().unwrap())
+    }
+
+    fn stats(&self) -> Result<WatcherStats> {
+        let (tx, rx) = mpsc::channel();
+
+        // we expect the event loop to live and reply => unwraps must not panic
+        self.0.send(EventLoopMsg::Stats(tx)).unwrap();
+        Ok(rx.recv().unwrap())
+    }
+}
+
+impl Drop for FanotifyWatcher {
+    fn drop(&mut self) {
+        // the event loop may be gone already if it panicked
+        let _ = self.0.send(EventLoopMsg::Shutdown);
+    }
+}
@@ -0,0 +1,153 @@
+//! Glob patterns selecting the paths below a watched directory
+//!
+//! A [`PathFilter`](struct.PathFilter.html) is passed to
+//! [`Watcher::watch_with`](../trait.Watcher.html#method.watch_with) as part of the
+//! [`WatchOptions`](../struct.WatchOptions.html) of a watch:
+//!
+//! ```no_run
+//! # use notify::{PathFilter, RecommendedWatcher, RecursiveMode, Watcher, WatchOptions};
+//! # use std::path::Path;
+//! # use std::sync::mpsc::channel;
+//! # fn main() {
+//! # let (tx, _rx) = channel();
+//! let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).unwrap();
+//! let filter = PathFilter::new()
+//!     .exclude("target").unwrap()
+//!     .exclude("**/node_modules").unwrap()
+//!     .exclude("**/.git").unwrap();
+//!
+//! watcher.watch_with(Path::new("/home/test/monorepo"), WatchOptions {
+//!     recursive_mode: RecursiveMode::Recursive,
+//!     filter: filter,
+//!     ..WatchOptions::default()
+//! }).unwrap();
+//! # }
+//! ```
+
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+use super::{Error, Result};
+
+/// Include and exclude glob patterns for the paths below a watched directory
+///
+/// Patterns are matched against the path relative to the watched directory, for example
+/// `src/lib.rs`. `*` doesn't match `/`, use `**` to match any number of directories.
+///
+/// A directory matching an exclude pattern is never descended into: the `INotifyWatcher` doesn't
+/// install watches below it and the `PollWatcher` doesn't scan it, including when the directory is
+/// created after the watch was added. No events are delivered for excluded paths.
+///
+/// If include patterns are set, events are only delivered for paths matching at least one of
+/// them. Include patterns don't stop directories from being descended into, so `**/*.rs` still
+/// watches every directory, but only reports Rust files.
+#[derive(Clone, Debug, Default)]
+pub struct PathFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl PathFilter {
+    /// Create a filter letting every path through.
+    pub fn new() -> PathFilter {
+        PathFilter::default()
+    }
+
+    /// Add an include pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidConfig` error if `pattern` is not a valid glob.
+    pub fn include(mut self, pattern: &str) -> Result<PathFilter> {
+        self.include.push(try!(compile(pattern)));
+        Ok(self)
+    }
+
+    /// Add an exclude pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidConfig` error if `pattern` is not a valid glob.
+    pub fn exclude(mut self, pattern: &str) -> Result<PathFilter> {
+        self.exclude.push(try!(compile(pattern)));
+        Ok(self)
+    }
+
+    /// Returns `true` if the filter has no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Returns `true` if `relative` or one of its parent directories matches an exclude pattern.
+    pub fn is_excluded(&self, relative: &Path) -> bool {
+        if self.exclude.is_empty() {
+            return false;
+        }
+        let mut path = Some(relative);
+        while let Some(p) = path {
+            if p.as_os_str().is_empty() {
+                break;
+            }
+            if self.exclude.iter().any(|pattern| pattern.matches_path_with(p, options())) {
+                return true;
+            }
+            path = p.parent();
+        }
+        false
+    }
+
+    /// Returns `true` if events for `relative` should be delivered.
+    ///
+    /// The watched directory itself, an empty `relative` path, always matches.
+    pub fn matches(&self, relative: &Path) -> bool {
+        if relative.as_os_str().is_empty() {
+            return true;
+        }
+        if self.is_excluded(relative) {
+            return false;
+        }
+        self.include.is_empty() ||
+        self.include.iter().any(|pattern| pattern.matches_path_with(relative, options()))
+    }
+}
+
+fn compile(pattern: &str) -> Result<Pattern> {
+    Pattern::new(pattern)
+        .map_err(|e| Error::invalid_config(&format!("invalid glob `{}`: {}", pattern, e)))
+}
+
+fn options() -> MatchOptions {
+    MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    }
+}
+
+#[test]
+fn exclude_prunes_descendants() {
+    let filter = PathFilter::new().exclude("target").unwrap().exclude("**/node_modules").unwrap();
+
+    assert!(filter.is_excluded(Path::new("target")));
+    assert!(filter.is_excluded(Path::new("target/debug/build")));
+    assert!(filter.is_excluded(Path::new("node_modules")));
+    assert!(filter.is_excluded(Path::new("web/node_modules/left-pad")));
+    assert!(!filter.is_excluded(Path::new("src/target")));
+    assert!(!filter.is_excluded(Path::new("src")));
+    assert!(filter.matches(Path::new("")));
+}
+
+#[test]
+fn include_selects_events() {
+    let filter = PathFilter::new().include("**/*.rs").unwrap().exclude("target").unwrap();
+
+    assert!(filter.matches(Path::new("lib.rs")));
+    assert!(filter.matches(Path::new("src/lib.rs")));
+    assert!(!filter.matches(Path::new("src/lib.c")));
+    assert!(!filter.matches(Path::new("target/out.rs")));
+    assert!(!filter.is_excluded(Path::new("src")));
+}
+
+#[test]
+fn invalid_pattern() {
+    assert!(PathFilter::new().include("[").is_err());
+}
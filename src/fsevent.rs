@@ -24,11 +24,12 @@ use std::path::{Path, PathBuf};
 use std::slice;
 use std::str::from_utf8;
 use std::sync::{Arc, Mutex};
-use std::sync::mpsc::{channel, Sender, Receiver};
+use std::sync::mpsc::{channel, Receiver};
 use std::thread;
 use std::time::Duration;
-use super::{Error, RawEvent, DebouncedEvent, op, Result, Watcher, RecursiveMode};
-use super::debounce::{Debounce, EventTx};
+use super::{Config, Error, EventHandler, RawEvent, DebouncedEvent, op, Result, Watcher,
+            RecursiveMode};
+use super::debounce::EventTx;
 
 /// FSEvents-based `Watcher` implementation
 pub struct FsEventWatcher {
@@ -74,7 +75,9 @@ fn send_pending_rename_event(event: Option<RawEvent>, event_tx: &mut EventTx) {
         event_tx.send(RawEvent {
             path: e.path,
             op: e.op,
+            kind: None,
             cookie: None,
+            pid: None,
         });
     }
 }
@@ -135,14 +138,14 @@ impl FsEventWatcher {
         };
         match self.recursive_info.remove(&p) {
             Some(_) => Ok(()),
-            None => Err(Error::WatchNotFound),
+            None => Err(Error::watch_not_found().add_path(p)),
         }
     }
 
     // https://github.com/thibaudgg/rb-fsevent/blob/master/ext/fsevent_watch/main.c
     fn append_path<P: AsRef<Path>>(&mut self, path: P, recursive_mode: RecursiveMode) -> Result<()> {
 		if !path.as_ref().exists() {
-			 return Err(Error::PathNotFound);
+			 return Err(Error::path_not_found().add_path(path.as_ref().to_owned()));
 		}
         let str_path = path.as_ref().to_str().unwrap();
         unsafe {
@@ -157,7 +160,7 @@ impl FsEventWatcher {
 
     fn run(&mut self) -> Result<()> {
         if unsafe { cf::CFArrayGetCount(self.paths) } == 0 {
-            return Err(Error::PathNotFound);
+            return Err(Error::path_not_found());
         }
 
         // done channel is used to sync quit status of runloop thread
@@ -270,7 +273,9 @@ pub unsafe extern "C" fn callback(
                 event_tx.send(RawEvent {
                     path: None,
                     op: Ok(op::RESCAN),
+                    kind: None,
                     cookie: None,
+                    pid: None,
                 });
             }
 
@@ -282,7 +287,9 @@ pub unsafe extern "C" fn callback(
                             event_tx.send(RawEvent {
                                 op: Ok(translate_flags(flag)),
                                 path: Some(path),
+                                kind: None,
                                 cookie: Some((id - 1) as u32),
+                                pid: None,
                             });
                             rename_event = None;
                         } else {
@@ -290,14 +297,18 @@ pub unsafe extern "C" fn callback(
                             rename_event = Some(RawEvent {
                                 path: Some(path),
                                 op: Ok(translate_flags(flag)),
+                                kind: None,
                                 cookie: Some(id as u32),
+                                pid: None,
                             });
                         }
                     } else {
                         rename_event = Some(RawEvent {
                             path: Some(path),
                             op: Ok(translate_flags(flag)),
+                            kind: None,
                             cookie: Some(id as u32),
+                            pid: None,
                         });
                     }
                 } else {
@@ -307,7 +318,9 @@ pub unsafe extern "C" fn callback(
                     event_tx.send(RawEvent {
                         op: Ok(translate_flags(flag)),
                         path: Some(path),
+                        kind: None,
                         cookie: None,
+                        pid: None,
                     });
                 }
             }
@@ -319,7 +332,7 @@ pub unsafe extern "C" fn callback(
 
 
 impl Watcher for FsEventWatcher {
-    fn new_raw(tx: Sender<RawEvent>) -> Result<FsEventWatcher> {
+    fn new_raw<F: EventHandler<RawEvent>>(handler: F) -> Result<FsEventWatcher> {
         Ok(FsEventWatcher {
             paths: unsafe {
                 cf::CFArrayCreateMutable(cf::kCFAllocatorDefault, 0, &cf::kCFTypeArrayCallBacks)
@@ -327,14 +340,14 @@ impl Watcher for FsEventWatcher {
             since_when: fs::kFSEventStreamEventIdSinceNow,
             latency: 0.0,
             flags: fs::kFSEventStreamCreateFlagFileEvents | fs::kFSEventStreamCreateFlagNoDefer,
-            event_tx: Arc::new(Mutex::new(EventTx::Raw { tx: tx })),
+            event_tx: Arc::new(Mutex::new(EventTx::raw(handler))),
             runloop: None,
             context: None,
             recursive_info: HashMap::new(),
         })
     }
 
-    fn new(tx: Sender<DebouncedEvent>, delay: Duration) -> Result<FsEventWatcher> {
+    fn new<F: EventHandler<DebouncedEvent>>(handler: F, delay: Duration) -> Result<FsEventWatcher> {
         Ok(FsEventWatcher {
             paths: unsafe {
                 cf::CFArrayCreateMutable(cf::kCFAllocatorDefault, 0, &cf::kCFTypeArrayCallBacks)
@@ -342,17 +355,14 @@ impl Watcher for FsEventWatcher {
             since_when: fs::kFSEventStreamEventIdSinceNow,
             latency: 0.0,
             flags: fs::kFSEventStreamCreateFlagFileEvents | fs::kFSEventStreamCreateFlagNoDefer,
-            event_tx: Arc::new(Mutex::new(EventTx::Debounced {
-                tx: tx.clone(),
-                debounce: Debounce::new(delay, tx),
-            })),
+            event_tx: Arc::new(Mutex::new(EventTx::debounced(handler, delay))),
             runloop: None,
             context: None,
             recursive_info: HashMap::new(),
         })
     }
 
-    fn watch<P: AsRef<Path>>(&mut self, path: P, recursive_mode: RecursiveMode) -> Result<()> {
+    fn watch(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()> {
         self.stop();
         let result = self.append_path(path, recursive_mode);  
         // ignore return error: may be empty path list
@@ -360,13 +370,20 @@ impl Watcher for FsEventWatcher {
         result
     }
 
-    fn unwatch<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+    fn unwatch(&mut self, path: &Path) -> Result<()> {
         self.stop();
         let result = self.remove_path(path);
         // ignore return error: may be empty path list
         let _ = self.run();
         result
     }
+
+    fn configure(&mut self, option: Config) -> Result<bool> {
+        match option {
+            Config::DebounceDelay(delay) => Ok(self.event_tx.lock().unwrap().set_delay(delay)),
+            Config::PollInterval(_) | Config::RenameWindow(_) => Ok(false),
+        }
+    }
 }
 
 impl Drop for FsEventWatcher {
@@ -387,12 +404,12 @@ fn test_fsevent_watcher_drop() {
 
     {
         let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).unwrap();
-        watcher.watch("../../", RecursiveMode::Recursive).unwrap();
+        watcher.watch(Path::new("../../"), RecursiveMode::Recursive).unwrap();
         thread::sleep(Duration::from_millis(2000));
         println!("is running -> {}", watcher.is_running());
 
         thread::sleep(Duration::from_millis(1000));
-        watcher.unwatch("../..").unwrap();
+        watcher.unwatch(Path::new("../..")).unwrap();
         println!("is running -> {}", watcher.is_running());
     }
 
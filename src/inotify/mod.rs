@@ -8,53 +8,341 @@ extern crate inotify as inotify_sys;
 extern crate libc;
 extern crate walkdir;
 
-use mio::{self, EventLoop};
+use mio::{self, EventLoop, EventLoopConfig};
 use self::inotify_sys::wrapper::{self, INotify, Watch};
-use self::walkdir::WalkDir;
-use std::collections::HashMap;
+use self::walkdir::{WalkDir, WalkDirIterator};
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::fs::metadata;
+use std::fs::{self, metadata, symlink_metadata};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Sender};
 use std::mem;
-use std::thread;
 use std::thread::Builder as ThreadBuilder;
-use std::time::Duration;
-use super::{Error, RawEvent, DebouncedEvent, op, Op, Result, Watcher, RecursiveMode};
-use super::debounce::{Debounce, EventTx};
+use std::time::{Duration, Instant};
+use super::{Config, Error, ErrorKind, EventHandler, RawEvent, DebouncedEvent, op, Op,
+            Result, SymlinkPolicy, Watcher, WatcherBuilder, WatcherStats, WatchOptions,
+            RecursiveMode};
+use super::event::{AccessKind, AccessMode, CreateKind, DataChange, EventKind, MetadataKind,
+                   ModifyKind, RemoveKind, RenameMode};
+use super::debounce::EventTx;
+use super::poll::WatchData;
+use super::walk;
 
 mod flags;
 
 const INOTIFY: mio::Token = mio::Token(0);
 
+/// Default time to wait for the `IN_MOVED_TO` half of a rename
+const DEFAULT_RENAME_WINDOW_MS: u64 = 10;
+
+/// Time during which an event for a path reported by a synthetic `CREATE` event is considered to
+/// be a duplicate
+const SYNTHETIC_CREATE_WINDOW_MS: u64 = 1000;
+
+/// Resolution of the event loop timer, which only runs while a rename is pending or directories
+/// are polled
+const TIMER_TICK_MS: u64 = 1;
+
 /// Watcher implementation based on inotify
+///
+/// Events are read on a thread running its own event loop, see `UnthreadedINotifyWatcher` to
+/// read them from an existing event loop instead.
 pub struct INotifyWatcher(mio::Sender<EventLoopMsg>);
 
+/// Watcher implementation based on inotify, driven by the caller's event loop
+///
+/// No thread is spawned: register the file descriptor returned by `as_raw_fd` for readability
+/// with the event loop, and call `process_events` whenever it's readable or the time returned by
+/// `timeout` elapsed. Renames are paired and recursive watches are maintained just like with the
+/// `INotifyWatcher`. Raw events are sent from `process_events`, debounced events are still emitted
+/// by the timer thread of the debouncer.
+///
+/// ```no_run
+/// # extern crate notify;
+/// use notify::{RecursiveMode, UnthreadedINotifyWatcher, Watcher};
+/// use std::os::unix::io::AsRawFd;
+/// use std::path::Path;
+/// use std::sync::mpsc::channel;
+///
+/// # fn main() {
+/// let (tx, rx) = channel();
+/// let mut watcher: UnthreadedINotifyWatcher = Watcher::new_raw(tx).unwrap();
+/// watcher.watch(Path::new("/home/test/notify"), RecursiveMode::Recursive).unwrap();
+/// let fd = watcher.as_raw_fd();
+///
+/// loop {
+///     // wait with epoll until `fd` is readable, for at most `watcher.timeout()`
+///     # let _ = fd;
+///     watcher.process_events();
+///     for event in rx.try_iter() {
+///         println!("{:?}", event);
+///     }
+/// }
+/// # }
+/// ```
+pub struct UnthreadedINotifyWatcher(INotifyHandler);
+
 struct INotifyHandler {
     inotify: Option<INotify>,
-    event_loop_tx: mio::Sender<EventLoopMsg>,
     event_tx: EventTx,
     watches: HashMap<PathBuf, (Watch, flags::Mask, bool)>,
     paths: HashMap<Watch, PathBuf>,
+    roots: HashMap<PathBuf, WatchOptions>,
+    mount_points: HashSet<PathBuf>,
+    absent_paths: HashSet<PathBuf>,
+    synthetic_creates: HashMap<PathBuf, Instant>,
     rename_event: Option<RawEvent>,
+    /// End of the window to pair the pending `IN_MOVED_FROM` event
+    rename_deadline: Option<Instant>,
+    rename_window: Duration,
+    polled: HashMap<PathBuf, WatchData>,
+    /// Next scan of the polled directories
+    poll_deadline: Option<Instant>,
+    poll_interval: Option<Duration>,
+    /// Event loop timer running until the earliest deadline, unused without a thread
+    timer: Option<mio::Timeout>,
+    /// One-shot watches that reported their event, with their options
+    finished: Vec<(PathBuf, WatchOptions)>,
+    access_ops: Op,
 }
 
 enum EventLoopMsg {
-    AddWatch(PathBuf, RecursiveMode, Sender<Result<()>>),
+    AddWatch(PathBuf, WatchOptions, Sender<Result<()>>),
     RemoveWatch(PathBuf, Sender<Result<()>>),
+    Configure(Config, Sender<Result<bool>>),
+    WatchedPaths(Sender<Vec<PathBuf>>),
+    Stats(Sender<WatcherStats>),
     Shutdown,
-    RenameTimeout(u32),
+}
+
+/// Send `event`, ending the one-shot watch it belongs to
+///
+/// The watched path is removed from `roots` right away, so that further events for it are
+/// dropped, and its watches are removed later on with the paths collected in `finished`.
+fn send_event(event_tx: &mut EventTx,
+              roots: &mut HashMap<PathBuf, WatchOptions>,
+              finished: &mut Vec<(PathBuf, WatchOptions)>,
+              event: RawEvent) {
+    if let Some(ref path) = event.path {
+        let root = match find_root(roots, path) {
+            Some((root, options)) if options.once => Some(root.clone()),
+            Some(_) => None,
+            // a one-shot watch ended earlier in this batch, only the second half of the rename
+            // that ended it is still delivered
+            None if event.cookie.is_none() => return,
+            None => None,
+        };
+        if let Some(root) = root {
+            let options = roots.remove(&root).unwrap();
+            finished.push((root, options));
+        }
+    }
+    event_tx.send(event);
 }
 
 #[inline]
-fn send_pending_rename_event(rename_event: &mut Option<RawEvent>, event_tx: &mut EventTx) {
+fn send_pending_rename_event(rename_event: &mut Option<RawEvent>,
+                             event_tx: &mut EventTx,
+                             roots: &mut HashMap<PathBuf, WatchOptions>,
+                             finished: &mut Vec<(PathBuf, WatchOptions)>) {
     let event = mem::replace(rename_event, None);
     if let Some(e) = event {
-        event_tx.send(RawEvent {
-                          path: e.path,
-                          op: Ok(op::REMOVE),
-                          cookie: None,
-                      });
+        // the path was moved out of the watched directories
+        if !watched_ops(roots, &e.path).contains(op::REMOVE) {
+            return;
+        }
+        let kind = EventKind::Remove(RemoveKind::Any);
+        send_event(event_tx,
+                   roots,
+                   finished,
+                   RawEvent {
+                       path: e.path,
+                       op: Ok(kind.into()),
+                       kind: Some(kind),
+                       cookie: None,
+                       pid: None,
+                   });
+    }
+}
+
+/// Returns `true` if events for `path` are reported by the watch of `root`
+fn covers(root: &Path, options: &WatchOptions, path: &Path) -> bool {
+    if options.follow_path || path == root {
+        return path == root;
+    }
+    path.starts_with(root) && (options.recursive_mode.is_recursive() || path.parent() == Some(root))
+}
+
+/// Returns the watched paths whose events include those for `path`
+fn matching_roots<'a>(roots: &'a HashMap<PathBuf, WatchOptions>,
+                      path: &'a Path)
+                      -> impl Iterator<Item = (&'a PathBuf, &'a WatchOptions)> + 'a {
+    roots.iter().filter(move |&(root, options)| covers(root, options, path))
+}
+
+/// Find the innermost watched path `path` belongs to, together with its options
+fn find_root<'a>(roots: &'a HashMap<PathBuf, WatchOptions>,
+                 path: &Path)
+                 -> Option<(&'a PathBuf, &'a WatchOptions)> {
+    roots.iter()
+        .filter(|&(root, options)| covers(root, options, path))
+        .max_by_key(|&(root, _)| root.components().count())
+}
+
+/// Returns whether `path` is excluded and whether its events are delivered
+///
+/// Overlapping watches are independent: `path` is only excluded if the filters of all the watches
+/// it belongs to exclude it, and its events are delivered if one of them matches it.
+fn filter_path(roots: &HashMap<PathBuf, WatchOptions>, path: &Option<PathBuf>) -> (bool, bool) {
+    if let Some(ref path) = *path {
+        let mut found = false;
+        let mut excluded = true;
+        let mut included = false;
+        for (root, options) in matching_roots(roots, path) {
+            let relative = path.strip_prefix(root).unwrap();
+            found = true;
+            excluded = excluded && options.filter.is_excluded(relative);
+            included = included || options.filter.matches(relative);
+        }
+        if found {
+            return (excluded, included);
+        }
+    }
+    (false, true)
+}
+
+/// Returns the operations requested by the watches delivering the events for `path`
+fn watched_ops(roots: &HashMap<PathBuf, WatchOptions>, path: &Option<PathBuf>) -> Op {
+    if let Some(ref path) = *path {
+        let mut found = false;
+        let mut ops = Op::empty();
+        for (root, options) in matching_roots(roots, path) {
+            found = true;
+            if options.filter.matches(path.strip_prefix(root).unwrap()) {
+                ops.insert(options.ops);
+            }
+        }
+        if found {
+            return ops;
+        }
+    }
+    Op::all()
+}
+
+/// Translate the operations requested by the user to inotify flags
+///
+/// Recursive watches always need to know about directories appearing and disappearing to keep
+/// their sub-directories watched, events for operations not requested are dropped in `ready`.
+fn watch_mask(ops: Op, is_recursive: bool, watch_self: bool) -> flags::Mask {
+    let mut mask = flags::Mask::empty();
+    if ops.contains(op::CHMOD) {
+        mask.insert(flags::IN_ATTRIB);
+    }
+    if ops.contains(op::CREATE) {
+        mask.insert(flags::IN_CREATE | flags::IN_MOVED_TO);
+    }
+    if ops.contains(op::REMOVE) {
+        mask.insert(flags::IN_DELETE | flags::IN_MOVED_FROM);
+        if watch_self {
+            mask.insert(flags::IN_DELETE_SELF);
+        }
+    }
+    if ops.contains(op::RENAME) {
+        mask.insert(flags::IN_MOVED_FROM | flags::IN_MOVED_TO);
+        if watch_self {
+            mask.insert(flags::IN_MOVE_SELF);
+        }
+    }
+    if ops.contains(op::WRITE) {
+        mask.insert(flags::IN_MODIFY);
+    }
+    if ops.contains(op::CLOSE_WRITE) {
+        mask.insert(flags::IN_CLOSE_WRITE);
+    }
+    if ops.contains(op::ACCESS) {
+        mask.insert(flags::IN_ACCESS);
+    }
+    if ops.contains(op::OPEN) {
+        mask.insert(flags::IN_OPEN);
+    }
+    if ops.contains(op::CLOSE_NOWRITE) {
+        mask.insert(flags::IN_CLOSE_NOWRITE);
+    }
+    if is_recursive {
+        mask.insert(flags::IN_CREATE | flags::IN_DELETE | flags::IN_MOVED_FROM |
+                    flags::IN_MOVED_TO);
+    }
+    mask
+}
+
+/// Translate the operations requested for a followed path to inotify flags for its parent
+/// directory
+///
+/// A file renamed over the followed path is reported as written.
+fn follow_mask(ops: Op) -> flags::Mask {
+    let mut mask = watch_mask(ops, false, false);
+    if ops.contains(op::WRITE) {
+        mask.insert(flags::IN_MOVED_TO);
+    }
+    mask
+}
+
+/// Returns the paths followed through the watch of directory `dir`
+fn followed_paths<'a>(roots: &'a HashMap<PathBuf, WatchOptions>,
+                      dir: &'a Path)
+                      -> impl Iterator<Item = (&'a PathBuf, &'a WatchOptions)> + 'a {
+    roots.iter().filter(move |&(root, options)| options.follow_path && root.parent() == Some(dir))
+}
+
+/// Returns `true` if `path` is watched as a followed path
+fn is_followed(roots: &HashMap<PathBuf, WatchOptions>, path: &Option<PathBuf>) -> bool {
+    path.as_ref()
+        .and_then(|path| roots.get(path))
+        .map_or(false, |options| options.follow_path)
+}
+
+/// Returns the watched paths that need an inotify watch of `path`
+///
+/// Each watched path owns the watches it needs, a watch is kept as long as one of its owners is
+/// watched and its flags are the union of what its owners need.
+fn watch_owners<'a>(roots: &'a HashMap<PathBuf, WatchOptions>,
+                    path: &'a Path)
+                    -> impl Iterator<Item = (&'a PathBuf, &'a WatchOptions)> + 'a {
+    roots.iter().filter(move |&(root, options)| if options.follow_path {
+        root.parent() == Some(path)
+    } else if path == root {
+        true
+    } else {
+        options.recursive_mode.is_recursive() && path.starts_with(root) &&
+        !options.filter.is_excluded(path.strip_prefix(root).unwrap())
+    })
+}
+
+/// Returns `true` if `path` still has to be watched for one of the watched paths
+fn is_watch_needed(roots: &HashMap<PathBuf, WatchOptions>, path: &Path) -> bool {
+    watch_owners(roots, path).next().is_some()
+}
+
+#[inline]
+fn create_kind(event: &wrapper::Event) -> EventKind {
+    if event.is_dir() {
+        EventKind::Create(CreateKind::Folder)
+    } else {
+        EventKind::Create(CreateKind::File)
+    }
+}
+
+#[inline]
+fn remove_kind(event: &wrapper::Event) -> EventKind {
+    if event.is_delete_self() {
+        // inotify doesn't flag IN_DELETE_SELF events with IN_ISDIR
+        EventKind::Remove(RemoveKind::Any)
+    } else if event.is_dir() {
+        EventKind::Remove(RemoveKind::Folder)
+    } else {
+        EventKind::Remove(RemoveKind::File)
     }
 }
 
@@ -62,13 +350,14 @@ fn send_pending_rename_event(rename_event: &mut Option<RawEvent>, event_tx: &mut
 fn add_watch_by_event(path: &Option<PathBuf>,
                       event: &wrapper::Event,
                       watches: &HashMap<PathBuf, (Watch, flags::Mask, bool)>,
-                      add_watches: &mut Vec<PathBuf>) {
+                      add_watches: &mut Vec<(PathBuf, bool)>,
+                      scan: bool) {
     if let Some(ref path) = *path {
         if event.is_dir() {
             if let Some(parent_path) = path.parent() {
                 if let Some(&(_, _, is_recursive)) = watches.get(parent_path) {
                     if is_recursive {
-                        add_watches.push(path.to_owned());
+                        add_watches.push((path.to_owned(), scan));
                     }
                 }
             }
@@ -79,238 +368,963 @@ fn add_watch_by_event(path: &Option<PathBuf>,
 #[inline]
 fn remove_watch_by_event(path: &Option<PathBuf>,
                          watches: &HashMap<PathBuf, (Watch, flags::Mask, bool)>,
+                         polled: &HashMap<PathBuf, WatchData>,
                          remove_watches: &mut Vec<PathBuf>) {
     if let Some(ref path) = *path {
-        if watches.contains_key(path) {
+        if watches.contains_key(path) || polled.contains_key(path) {
             remove_watches.push(path.to_owned());
         }
     }
 }
 
+/// Clean up after a watch removed by the kernel, collecting the watched paths that were dropped
+fn forget_watch(wd: Watch,
+                watches: &mut HashMap<PathBuf, (Watch, flags::Mask, bool)>,
+                paths: &mut HashMap<Watch, PathBuf>,
+                roots: &mut HashMap<PathBuf, WatchOptions>,
+                dropped: &mut Vec<PathBuf>) {
+    if let Some(path) = paths.remove(&wd) {
+        // the path may have been watched again in the meantime
+        if watches.get(&path).map_or(false, |&(w, _, _)| w == wd) {
+            watches.remove(&path);
+            let followed: Vec<PathBuf> =
+                followed_paths(roots, &path).map(|(root, _)| root.clone()).collect();
+            for root in followed {
+                roots.remove(&root);
+                dropped.push(root);
+            }
+            if roots.remove(&path).is_some() {
+                dropped.push(path);
+            }
+        }
+    }
+}
+
+/// Returns `true` if the parent directory of `path` is watched recursively
+fn is_recursive_parent(watches: &HashMap<PathBuf, (Watch, flags::Mask, bool)>,
+                       path: &Path)
+                       -> bool {
+    path.parent()
+        .and_then(|parent| watches.get(parent))
+        .map_or(false, |&(_, _, is_recursive)| is_recursive)
+}
+
+/// Returns `path` moved from directory `from` to `to`
+fn renamed_path(path: &Path, from: &Path, to: &Path) -> PathBuf {
+    match path.strip_prefix(from) {
+        Ok(relative) if !relative.as_os_str().is_empty() => to.join(relative),
+        _ => to.to_path_buf(),
+    }
+}
+
+/// Move the watches of directory `from` and of the directories below it to `to`
+fn rename_watches(from: &Path,
+                  to: &Path,
+                  watches: &mut HashMap<PathBuf, (Watch, flags::Mask, bool)>,
+                  paths: &mut HashMap<Watch, PathBuf>,
+                  roots: &mut HashMap<PathBuf, WatchOptions>,
+                  mount_points: &mut HashSet<PathBuf>) {
+    let moved: Vec<PathBuf> = watches.keys().filter(|p| p.starts_with(from)).cloned().collect();
+    for old in moved {
+        let new = renamed_path(&old, from, to);
+        if let Some(watch) = watches.remove(&old) {
+            paths.insert(watch.0, new.clone());
+            watches.insert(new.clone(), watch);
+        }
+        if let Some(options) = roots.remove(&old) {
+            roots.insert(new.clone(), options);
+        }
+        if mount_points.remove(&old) {
+            mount_points.insert(new);
+        }
+    }
+}
+
+/// Returns `true` if a synthetic `CREATE` event was recently sent for `path`
+fn is_synthetic_create(synthetic_creates: &mut HashMap<PathBuf, Instant>,
+                       path: &Option<PathBuf>)
+                       -> bool {
+    let window = Duration::from_millis(SYNTHETIC_CREATE_WINDOW_MS);
+    match path.as_ref().and_then(|path| synthetic_creates.remove(path)) {
+        Some(created) => created.elapsed() < window,
+        None => false,
+    }
+}
+
+#[inline]
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + duration.subsec_nanos() as u64 / 1_000_000
+}
+
+/// Returns `true` if `path` is on another filesystem than its parent directory
+fn is_mount_point(path: &Path) -> bool {
+    match (metadata(path), path.parent().map(metadata)) {
+        (Ok(m), Some(Ok(parent))) => m.dev() != parent.dev(),
+        (Ok(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Send `UNMOUNT` and `WATCH_DROPPED` events collected while reading a batch of inotify events
+fn send_lifecycle_events(unmounted: Vec<PathBuf>, dropped: Vec<PathBuf>, event_tx: &mut EventTx) {
+    for path in unmounted {
+        event_tx.send(RawEvent {
+                          path: Some(path),
+                          op: Ok(op::UNMOUNT),
+                          kind: None,
+                          cookie: None,
+                          pid: None,
+                      });
+    }
+    for path in dropped {
+        event_tx.send(RawEvent {
+                          path: Some(path),
+                          op: Ok(op::WATCH_DROPPED),
+                          kind: None,
+                          cookie: None,
+                          pid: None,
+                      });
+    }
+}
+
 impl mio::Handler for INotifyHandler {
     type Timeout = ();
     type Message = EventLoopMsg;
 
     fn ready(&mut self,
-             _event_loop: &mut EventLoop<INotifyHandler>,
+             event_loop: &mut EventLoop<INotifyHandler>,
              token: mio::Token,
              events: mio::EventSet) {
         match token {
             INOTIFY => {
                 assert!(events.is_readable());
+                self.handle_events();
+                self.schedule_timer(event_loop);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn notify(&mut self, event_loop: &mut EventLoop<INotifyHandler>, msg: EventLoopMsg) {
+        match msg {
+            EventLoopMsg::AddWatch(path, options, tx) => {
+                let result = self.add_root(path, options);
+                self.schedule_timer(event_loop);
+                let _ = tx.send(result);
+            }
+            EventLoopMsg::RemoveWatch(path, tx) => {
+                let _ = tx.send(self.remove_root(path));
+            }
+            EventLoopMsg::Configure(config, tx) => {
+                let _ = tx.send(Ok(self.configure(config)));
+            }
+            EventLoopMsg::WatchedPaths(tx) => {
+                let _ = tx.send(self.watched_paths());
+            }
+            EventLoopMsg::Stats(tx) => {
+                let _ = tx.send(self.stats());
+            }
+            EventLoopMsg::Shutdown => {
+                self.shutdown();
+                event_loop.shutdown();
+            }
+        }
+    }
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<INotifyHandler>, _: ()) {
+        self.timer = None;
+        self.handle_timers();
+        self.schedule_timer(event_loop);
+    }
+}
+
+/// return `DirEntry` when it is a directory
+fn filter_dir(e: walkdir::Result<walkdir::DirEntry>) -> Option<walkdir::DirEntry> {
+    if let Ok(e) = e {
+        if let Ok(metadata) = e.metadata() {
+            if metadata.is_dir() {
+                return Some(e);
+            }
+        }
+    }
+    None
+}
+
+impl INotifyHandler {
+    fn new(event_tx: EventTx, builder: &WatcherBuilder) -> Result<INotifyHandler> {
+        let inotify = try!(INotify::init().map_err(|e| {
+            if e.raw_os_error() == Some(libc::EMFILE) {
+                // EMFILE means either the per-user instance limit or the per-process file
+                // descriptor limit was reached, they can't be told apart
+                Error::new(ErrorKind::MaxInstances)
+            } else {
+                Error::io(e)
+            }
+        }));
+
+        // the debouncer doesn't handle the opt-in operations, so they aren't requested from inotify
+        let access_ops = match event_tx {
+            EventTx::Raw { .. } => builder.access_ops.unwrap_or(Op::empty()),
+            EventTx::Debounced { .. } => Op::empty(),
+        };
+
+        Ok(INotifyHandler {
+            inotify: Some(inotify),
+            event_tx: event_tx,
+            watches: HashMap::new(),
+            paths: HashMap::new(),
+            roots: HashMap::new(),
+            mount_points: HashSet::new(),
+            absent_paths: HashSet::new(),
+            synthetic_creates: HashMap::new(),
+            rename_event: None,
+            rename_deadline: None,
+            rename_window: builder.rename_window
+                .unwrap_or(Duration::from_millis(DEFAULT_RENAME_WINDOW_MS)),
+            polled: HashMap::new(),
+            poll_deadline: None,
+            poll_interval: builder.poll_fallback,
+            timer: None,
+            finished: Vec::new(),
+            access_ops: access_ops,
+        })
+    }
+
+    /// The inotify file descriptor, `-1` once the handler was shut down
+    fn raw_fd(&self) -> RawFd {
+        self.inotify.as_ref().map_or(-1, |inotify| inotify.fd)
+    }
+
+    /// Read and handle the next batch of inotify events, returns `false` if there were none
+    fn handle_events(&mut self) -> bool {
+        let mut add_watches = Vec::new();
+        let mut remove_watches = Vec::new();
+        let mut unmounted = Vec::new();
+        let mut dropped = Vec::new();
+
+        if let Some(ref mut inotify) = self.inotify {
+            match inotify.available_events() {
+                Ok(events) => {
+                    if events.is_empty() {
+                        return false;
+                    }
+
+                    for event in events {
+                        if event.is_queue_overflow() {
+                            self.event_tx.send(RawEvent {
+                                                   path: None,
+                                                   op: Ok(op::RESCAN),
+                                                   kind: None,
+                                                   cookie: None,
+                                                   pid: None,
+                                               });
+                        }
+
+                        let path = if event.name.as_os_str().is_empty() {
+                            match self.paths.get(&event.wd) {
+                                Some(p) => Some(p.clone()),
+                                None => None,
+                            }
+                        } else {
+                            self.paths.get(&event.wd).map(|root| root.join(&event.name))
+                        };
 
-                let mut add_watches = Vec::new();
-                let mut remove_watches = Vec::new();
-
-                if let Some(ref mut inotify) = self.inotify {
-                    match inotify.available_events() {
-                        Ok(events) => {
-                            assert!(!events.is_empty());
-
-                            for event in events {
-                                if event.is_queue_overflow() {
-                                    self.event_tx.send(RawEvent {
-                                                           path: None,
-                                                           op: Ok(op::RESCAN),
-                                                           cookie: None,
-                                                       });
+                        if event.is_unmount() {
+                            // every watch on the filesystem gets IN_UNMOUNT, only the
+                            // mount point and the watched paths are reported
+                            if let Some(ref path) = path {
+                                if self.mount_points.contains(path) ||
+                                   self.roots.contains_key(path) {
+                                    unmounted.push(path.clone());
                                 }
+                            }
+                        }
+                        if event.is_ignored() {
+                            // the kernel removed the watch, because it was removed with
+                            // `rm_watch` or because the path was deleted or unmounted
+                            forget_watch(event.wd,
+                                         &mut self.watches,
+                                         &mut self.paths,
+                                         &mut self.roots,
+                                         &mut dropped);
+                        }
+                        if event.is_unmount() || event.is_ignored() {
+                            continue;
+                        }
 
-                                let path = if event.name.as_os_str().is_empty() {
-                                    match self.paths.get(&event.wd) {
-                                        Some(p) => Some(p.clone()),
-                                        None => None,
-                                    }
-                                } else {
-                                    self.paths.get(&event.wd).map(|root| root.join(&event.name))
-                                };
-
-                                if event.is_moved_from() {
-                                    send_pending_rename_event(&mut self.rename_event,
-                                                              &mut self.event_tx);
-                                    remove_watch_by_event(&path,
-                                                          &self.watches,
-                                                          &mut remove_watches);
-                                    self.rename_event = Some(RawEvent {
-                                                                 path: path,
-                                                                 op: Ok(op::RENAME),
-                                                                 cookie: Some(event.cookie),
-                                                             });
+                        // the path was already reported when its parent directory was
+                        // scanned, and is already watched if it's a directory
+                        if (event.is_create() || event.is_moved_to()) &&
+                           is_synthetic_create(&mut self.synthetic_creates, &path) {
+                            continue;
+                        }
+                        if event.is_delete() || event.is_moved_from() {
+                            if let Some(ref path) = path {
+                                self.synthetic_creates.remove(path);
+                            }
+                        }
+
+                        // excluded directories are never watched, so skipping their
+                        // events also keeps newly created ones unwatched
+                        let (excluded, included) = filter_path(&self.roots, &path);
+                        let ops = watched_ops(&self.roots, &path);
+                        if excluded {
+                            continue;
+                        }
+                        // the parent directory of a followed path is only watched for
+                        // that path
+                        if let Some(ref path) = path {
+                            if find_root(&self.roots, path).is_none() {
+                                continue;
+                            }
+                        }
+
+                        // a followed path doesn't take part in renames, a file renamed
+                        // over it replaces it
+                        if is_followed(&self.roots, &path) &&
+                           (event.is_create() || event.is_moved_to() ||
+                            event.is_delete() || event.is_moved_from()) {
+                            let followed = path.clone().unwrap();
+                            let kind = if event.is_create() || event.is_moved_to() {
+                                if self.absent_paths.remove(&followed) {
+                                    create_kind(event)
                                 } else {
-                                    let mut o = Op::empty();
-                                    let mut c = None;
-                                    if event.is_moved_to() {
-                                        let rename_event = mem::replace(&mut self.rename_event,
-                                                                        None);
-                                        if let Some(e) = rename_event {
-                                            if e.cookie == Some(event.cookie) {
-                                                self.event_tx.send(e);
-                                                o.insert(op::RENAME);
-                                                c = Some(event.cookie);
-                                            } else {
-                                                o.insert(op::CREATE);
+                                    EventKind::Modify(ModifyKind::Data(DataChange::Any))
+                                }
+                            } else {
+                                self.absent_paths.insert(followed);
+                                remove_kind(event)
+                            };
+                            send_pending_rename_event(&mut self.rename_event,
+                                                      &mut self.event_tx,
+                                                      &mut self.roots,
+                                                      &mut self.finished);
+                            if included && ops.intersects(kind.into()) {
+                                send_event(&mut self.event_tx,
+                                           &mut self.roots,
+                                           &mut self.finished,
+                                           RawEvent {
+                                               path: path,
+                                               op: Ok(kind.into()),
+                                               kind: Some(kind),
+                                               cookie: None,
+                                               pid: None,
+                                           });
+                            }
+                            continue;
+                        }
+
+                        if event.is_moved_from() {
+                            send_pending_rename_event(&mut self.rename_event,
+                                                      &mut self.event_tx,
+                                                      &mut self.roots,
+                                                      &mut self.finished);
+                            remove_watch_by_event(&path,
+                                                  &self.watches,
+                                                  &self.polled,
+                                                  &mut remove_watches);
+                            if included && ops.intersects(op::RENAME | op::REMOVE) {
+                                let kind = EventKind::Modify(ModifyKind::Name(RenameMode::From));
+                                self.rename_event = Some(RawEvent {
+                                                             path: path,
+                                                             op: Ok(kind.into()),
+                                                             kind: Some(kind),
+                                                             cookie: Some(event.cookie),
+                                                             pid: None,
+                                                         });
+                            }
+                        } else {
+                            let mut kind = None;
+                            let mut c = None;
+                            if event.is_moved_to() {
+                                let mut moved_watches = false;
+                                let rename_event = mem::replace(&mut self.rename_event,
+                                                                None);
+                                if let Some(e) = rename_event {
+                                    if e.cookie == Some(event.cookie) {
+                                        if let (true, Some(from), Some(to)) =
+                                            (event.is_dir(), e.path.as_ref(), path.as_ref()) {
+                                            // the kernel keeps watching the moved
+                                            // directories, only their paths change
+                                            if self.watches.contains_key(from) &&
+                                               is_recursive_parent(&self.watches, to) {
+                                                remove_watches.retain(|p| p != from);
+                                                rename_watches(from,
+                                                               to,
+                                                               &mut self.watches,
+                                                               &mut self.paths,
+                                                               &mut self.roots,
+                                                               &mut self.mount_points);
+                                                // polled directories are polled again
+                                                // at their new path
+                                                let polled: Vec<PathBuf> = self.polled
+                                                    .keys()
+                                                    .filter(|p| p.starts_with(from))
+                                                    .cloned()
+                                                    .collect();
+                                                for old in polled {
+                                                    self.polled.remove(&old);
+                                                    let new = renamed_path(&old, from, to);
+                                                    add_watches.push((new, false));
+                                                }
+                                                moved_watches = true;
                                             }
-                                        } else {
-                                            o.insert(op::CREATE);
                                         }
-                                        add_watch_by_event(&path,
-                                                           event,
-                                                           &self.watches,
-                                                           &mut add_watches);
-                                    }
-                                    if event.is_move_self() {
-                                        o.insert(op::RENAME);
-                                    }
-                                    if event.is_create() {
-                                        o.insert(op::CREATE);
-                                        add_watch_by_event(&path,
-                                                           event,
-                                                           &self.watches,
-                                                           &mut add_watches);
-                                    }
-                                    if event.is_delete_self() || event.is_delete() {
-                                        o.insert(op::REMOVE);
-                                        remove_watch_by_event(&path,
-                                                              &self.watches,
-                                                              &mut remove_watches);
-                                    }
-                                    if event.is_modify() {
-                                        o.insert(op::WRITE);
-                                    }
-                                    if event.is_close_write() {
-                                        o.insert(op::CLOSE_WRITE);
-                                    }
-                                    if event.is_attrib() {
-                                        o.insert(op::CHMOD);
-                                    }
-
-                                    if !o.is_empty() {
-                                        send_pending_rename_event(&mut self.rename_event,
-                                                                  &mut self.event_tx);
-
-                                        self.event_tx.send(RawEvent {
-                                                               path: path,
-                                                               op: Ok(o),
-                                                               cookie: c,
-                                                           });
+                                        if watched_ops(&self.roots, &e.path)
+                                            .contains(op::RENAME) {
+                                            send_event(&mut self.event_tx,
+                                                       &mut self.roots,
+                                                       &mut self.finished,
+                                                       e);
+                                        }
+                                        let name = ModifyKind::Name(RenameMode::To);
+                                        kind = Some(EventKind::Modify(name));
+                                        c = Some(event.cookie);
+                                    } else {
+                                        kind = Some(create_kind(event));
                                     }
+                                } else {
+                                    kind = Some(create_kind(event));
+                                }
+                                // contents of a directory renamed within the watched
+                                // directories were already reported
+                                if !moved_watches {
+                                    add_watch_by_event(&path,
+                                                       event,
+                                                       &self.watches,
+                                                       &mut add_watches,
+                                                       c.is_none());
                                 }
                             }
-
-                            // When receiving only the first part of a move event (IN_MOVED_FROM) it is unclear
-                            // whether the second part (IN_MOVED_TO) will arrive because the file or directory
-                            // could just have been moved out of the watched directory. So it's necessary to wait
-                            // for possible subsequent events in case it's a complete move event but also to make sure
-                            // that the first part of the event is handled in a timely manner in case no subsequent events arrive.
-                            if let Some(ref rename_event) = self.rename_event {
-                                let event_loop_tx = self.event_loop_tx.clone();
-                                let cookie = rename_event.cookie.unwrap(); // unwrap is safe because rename_event is always set with some cookie
-                                thread::spawn(move || {
-                                                  thread::sleep(Duration::from_millis(10)); // wait up to 10 ms for a subsequent event
-                                                  event_loop_tx.send(EventLoopMsg::RenameTimeout(cookie)).unwrap();
-                                              });
+                            if event.is_move_self() {
+                                kind = Some(EventKind::Modify(ModifyKind::Name(RenameMode::From)));
                             }
-                        }
-                        Err(e) => {
-                            self.event_tx.send(RawEvent {
-                                                   path: None,
-                                                   op: Err(Error::Io(e)),
-                                                   cookie: None,
+                            if event.is_create() {
+                                kind = Some(create_kind(event));
+                                add_watch_by_event(&path,
+                                                   event,
+                                                   &self.watches,
+                                                   &mut add_watches,
+                                                   true);
+                            }
+                            if event.is_delete_self() || event.is_delete() {
+                                kind = Some(remove_kind(event));
+                                remove_watch_by_event(&path,
+                                                      &self.watches,
+                                                      &self.polled,
+                                                      &mut remove_watches);
+                            }
+                            if event.is_modify() {
+                                let data = ModifyKind::Data(DataChange::Content);
+                                kind = Some(EventKind::Modify(data));
+                            }
+                            if event.is_access() {
+                                kind = Some(EventKind::Access(AccessKind::Read));
+                            }
+                            if event.is_open() {
+                                kind = Some(EventKind::Access(AccessKind::Open(AccessMode::Any)));
+                            }
+                            if event.is_close_nowrite() {
+                                kind = Some(EventKind::Access(AccessKind::Close(AccessMode::Read)));
+                            }
+                            if event.is_close_write() {
+                                let close = AccessKind::Close(AccessMode::Write);
+                                kind = Some(EventKind::Access(close));
+                            }
+                            if event.is_attrib() {
+                                let metadata = ModifyKind::Metadata(MetadataKind::Any);
+                                kind = Some(EventKind::Modify(metadata));
+                            }
+
+                            if let Some(kind) = kind {
+                                send_pending_rename_event(&mut self.rename_event,
+                                                          &mut self.event_tx,
+                                                          &mut self.roots,
+                                                          &mut self.finished);
+
+                                if included && ops.intersects(kind.into()) {
+                                    send_event(&mut self.event_tx,
+                                               &mut self.roots,
+                                               &mut self.finished,
+                                               RawEvent {
+                                                   path: path,
+                                                   op: Ok(kind.into()),
+                                                   kind: Some(kind),
+                                                   cookie: c,
+                                                   pid: None,
                                                });
+                                }
+                            }
                         }
                     }
-                }
 
-                for path in remove_watches {
-                    let _ = self.remove_watch(path, true);
+                    send_lifecycle_events(unmounted, dropped, &mut self.event_tx);
+
+                    // When receiving only the first part of a move event (IN_MOVED_FROM) it is
+                    // unclear whether the second part (IN_MOVED_TO) will arrive because the file or
+                    // directory could just have been moved out of the watched directory. So it's
+                    // necessary to wait for possible subsequent events in case it's a complete move
+                    // event but also to make sure that the first part of the event is handled in a
+                    // timely manner in case no subsequent events arrive.
+                    self.rename_deadline = if self.rename_event.is_some() {
+                        Some(Instant::now() + self.rename_window)
+                    } else {
+                        None
+                    };
+                }
+                Err(e) => {
+                    self.event_tx.send(RawEvent {
+                                           path: None,
+                                           op: Err(Error::io(e)),
+                                           kind: None,
+                                           cookie: None,
+                                           pid: None,
+                                       });
+                    return false;
                 }
+            }
+        } else {
+            return false;
+        }
 
-                for path in add_watches {
-                    let _ = self.add_watch(path, true, false);
+        for path in remove_watches {
+            let _ = self.remove_watch(path, true);
+        }
+
+        for (path, scan) in add_watches {
+            if find_root(&self.roots, &path).is_none() {
+                // the one-shot watch the directory belongs to ended
+                continue;
+            }
+            match self.add_watch(path.clone(), true) {
+                Ok(()) => {
+                    if scan {
+                        self.scan_new_directory(&path);
+                    }
+                }
+                Err(err) => {
+                    if let ErrorKind::MaxFilesWatch = err.kind {
+                        // the directory stays unwatched, which would go unnoticed
+                        self.event_tx.send(RawEvent {
+                                               path: Some(path),
+                                               op: Err(err),
+                                               kind: None,
+                                               cookie: None,
+                                               pid: None,
+                                           });
+                    }
                 }
             }
-            _ => unreachable!(),
         }
+        self.remove_finished();
+        self.schedule_poll();
+
+        let window = Duration::from_millis(SYNTHETIC_CREATE_WINDOW_MS);
+        self.synthetic_creates.retain(|_, created| created.elapsed() < window);
+
+        true
     }
 
-    fn notify(&mut self, event_loop: &mut EventLoop<INotifyHandler>, msg: EventLoopMsg) {
-        match msg {
-            EventLoopMsg::AddWatch(path, recursive_mode, tx) => {
-                let _ = tx.send(self.add_watch(path, recursive_mode.is_recursive(), true));
+    fn configure(&mut self, config: Config) -> bool {
+        match config {
+            Config::DebounceDelay(delay) => self.event_tx.set_delay(delay),
+            Config::RenameWindow(window) => {
+                self.rename_window = window;
+                true
             }
-            EventLoopMsg::RemoveWatch(path, tx) => {
-                let _ = tx.send(self.remove_watch(path, false));
+            Config::PollInterval(_) => false,
+        }
+    }
+
+    /// Watch `path` for the user
+    fn add_root(&mut self, path: PathBuf, mut options: WatchOptions) -> Result<()> {
+        options.ops.insert(self.access_ops);
+        if let EventTx::Debounced { .. } = self.event_tx {
+            // the debouncer doesn't handle the opt-in operations
+            options.ops.remove(op::ACCESS | op::OPEN | op::CLOSE_NOWRITE);
+        }
+        let is_recursive = options.recursive_mode.is_recursive();
+        let follow_path = options.follow_path;
+        let previous = self.roots.insert(path.clone(), options);
+        let result = if follow_path {
+            self.follow(path.clone())
+        } else {
+            self.add_watch(path.clone(), is_recursive)
+        };
+        if result.is_err() {
+            let options = self.roots.remove(&path).unwrap();
+            let _ = self.release_root(path.clone(), &options);
+        }
+        if let Some(previous) = previous {
+            // watching the same path again replaces its options
+            let _ = self.release_root(path, &previous);
+        }
+        self.schedule_poll();
+        result
+    }
+
+    /// The watched paths, sorted
+    fn watched_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.roots.keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    fn stats(&self) -> WatcherStats {
+        WatcherStats {
+            roots: self.roots.len(),
+            descriptors: self.watches.len(),
+            polled_files: self.polled.values().map(WatchData::path_count).sum(),
+            max_user_watches: max_user_watches(),
+        }
+    }
+
+    /// Stop watching `path` for the user
+    fn remove_root(&mut self, path: PathBuf) -> Result<()> {
+        match self.roots.remove(&path) {
+            Some(options) => self.release_root(path, &options),
+            None => Err(Error::watch_not_found().add_path(path)),
+        }
+    }
+
+    /// Remove the watches only the watched path `path` needed, after it was removed from the
+    /// watched paths, and drop its flags from the watches it shares with other watched paths
+    fn release_root(&mut self, path: PathBuf, options: &WatchOptions) -> Result<()> {
+        let is_recursive = options.recursive_mode.is_recursive();
+        let owned = |dir: &Path| dir == path || (is_recursive && dir.starts_with(&path));
+        let dirs: Vec<PathBuf> = if options.follow_path {
+            self.absent_paths.remove(&path);
+            path.parent().map(Path::to_path_buf).into_iter().collect()
+        } else {
+            let roots = &self.roots;
+            self.polled.retain(|dir, _| !owned(dir) || is_watch_needed(roots, dir));
+            self.watches.keys().filter(|dir| owned(dir)).cloned().collect()
+        };
+        // the directories polled for `path` that another watched path still covers are polled for
+        // that one from now on
+        let polled: Vec<PathBuf> = self.polled
+            .iter()
+            .filter(|&(_, data)| data.root() == path)
+            .map(|(dir, _)| dir.clone())
+            .collect();
+        for dir in polled {
+            self.polled.remove(&dir);
+            if !is_watch_needed(&self.roots, &dir) {
+                continue;
             }
-            EventLoopMsg::Shutdown => {
-                let _ = self.remove_all_watches();
-                if let Some(inotify) = self.inotify.take() {
-                    let _ = inotify.close();
+            let (root, options) = match find_root(&self.roots, &dir) {
+                Some((root, options)) => (root.clone(), options.clone()),
+                None => continue,
+            };
+            // unless a directory above is polled for it already
+            let is_polled_above = self.polled
+                .iter()
+                .any(|(above, data)| dir.starts_with(above) && data.root() == root);
+            if is_polled_above {
+                continue;
+            }
+            if let Some(data) = WatchData::new(&dir, &root, &options, None, &mut self.event_tx) {
+                self.polled.insert(dir, data);
+            }
+        }
+        for dir in dirs {
+            if is_watch_needed(&self.roots, &dir) {
+                try!(self.add_single_watch(dir));
+            } else if let Some((w, _, _)) = self.watches.remove(&dir) {
+                self.paths.remove(&w);
+                self.mount_points.remove(&dir);
+                if let Some(ref inotify) = self.inotify {
+                    try!(inotify.rm_watch(w).map_err(|e| Error::io(e).add_path(dir)));
                 }
-                event_loop.shutdown();
             }
-            EventLoopMsg::RenameTimeout(cookie) => {
-                let current_cookie = self.rename_event.as_ref().and_then(|e| e.cookie);
-                // send pending rename event only if the rename event for which the timer has been created hasn't been handled already; otherwise ignore this timeout
-                if current_cookie == Some(cookie) {
-                    send_pending_rename_event(&mut self.rename_event, &mut self.event_tx);
+        }
+        Ok(())
+    }
+
+    /// Time until the next deadline, if the pending rename or the polled directories have one
+    fn next_timeout(&self) -> Option<Duration> {
+        let deadline = match (self.rename_deadline, self.poll_deadline) {
+            (Some(rename), Some(poll)) => Some(if rename < poll { rename } else { poll }),
+            (rename, poll) => rename.or(poll),
+        };
+        deadline.map(|deadline| {
+            let now = Instant::now();
+            if deadline > now {
+                deadline - now
+            } else {
+                Duration::from_millis(0)
+            }
+        })
+    }
+
+    /// Report the pending rename and scan the polled directories once their deadline passed
+    fn handle_timers(&mut self) {
+        let now = Instant::now();
+        if self.rename_deadline.map_or(false, |deadline| deadline <= now) {
+            // the second part of the rename didn't arrive in time
+            self.rename_deadline = None;
+            send_pending_rename_event(&mut self.rename_event,
+                                      &mut self.event_tx,
+                                      &mut self.roots,
+                                      &mut self.finished);
+        }
+        if self.poll_deadline.map_or(false, |deadline| deadline <= now) {
+            self.poll_deadline = None;
+            for (dir, data) in self.polled.iter_mut() {
+                if !self.roots.contains_key(data.root()) {
+                    // the one-shot watch the directory belongs to ended
+                    continue;
+                }
+                if !data.rescan(dir, &mut self.event_tx) {
+                    let root = data.root().to_path_buf();
+                    if let Some(options) = self.roots.remove(&root) {
+                        self.finished.push((root, options));
+                    }
                 }
             }
+            self.schedule_poll();
         }
+        self.remove_finished();
     }
-}
 
-/// return `DirEntry` when it is a directory
-fn filter_dir(e: walkdir::Result<walkdir::DirEntry>) -> Option<walkdir::DirEntry> {
-    if let Ok(e) = e {
-        if let Ok(metadata) = e.metadata() {
-            if metadata.is_dir() {
-                return Some(e);
+    /// Restart the event loop timer for the next deadline
+    fn schedule_timer(&mut self, event_loop: &mut EventLoop<INotifyHandler>) {
+        if let Some(timer) = self.timer.take() {
+            event_loop.clear_timeout(timer);
+        }
+        if let Some(timeout) = self.next_timeout() {
+            match event_loop.timeout_ms((), duration_ms(timeout)) {
+                Ok(timer) => self.timer = Some(timer),
+                Err(_) => {
+                    // the timer is full, don't wait for the second part of the rename, the
+                    // polled directories are scanned after the next batch of events
+                    self.rename_deadline = None;
+                    send_pending_rename_event(&mut self.rename_event,
+                                              &mut self.event_tx,
+                                              &mut self.roots,
+                                              &mut self.finished);
+                    self.remove_finished();
+                }
             }
         }
     }
-    None
-}
 
-impl INotifyHandler {
-    fn add_watch(&mut self, path: PathBuf, is_recursive: bool, mut watch_self: bool) -> Result<()> {
-        let metadata = try!(metadata(&path).map_err(Error::Io));
+    /// Remove the watches of the one-shot watches that reported their event
+    fn remove_finished(&mut self) {
+        for (root, options) in mem::replace(&mut self.finished, Vec::new()) {
+            let _ = self.release_root(root, &options);
+        }
+    }
+
+    fn shutdown(&mut self) {
+        let _ = self.remove_all_watches();
+        if let Some(inotify) = self.inotify.take() {
+            let _ = inotify.close();
+        }
+    }
+
+
+    fn add_watch(&mut self, path: PathBuf, is_recursive: bool) -> Result<()> {
+        let (root, options) = match find_root(&self.roots, &path) {
+            Some((root, options)) => (root.clone(), options.clone()),
+            None => (path.clone(), WatchOptions::default()),
+        };
+
+        let metadata = if options.symlinks == SymlinkPolicy::NoFollow {
+            symlink_metadata(&path)
+        } else {
+            metadata(&path)
+        };
+        let metadata = try!(metadata.map_err(|e| Error::io(e).add_path(path.clone())));
 
         if !metadata.is_dir() || !is_recursive {
-            return self.add_single_watch(path, false, true);
+            return match self.add_single_watch(path.clone()) {
+                Ok(()) => Ok(()),
+                Err(err) => self.poll_instead(path, err),
+            };
+        }
+
+        // the directories added by this call, to remove them again if a directory can't be
+        // watched, instead of leaving the tree partially watched
+        let mut added = Vec::new();
+        let mut entries = walk::walk(WalkDir::new(path), &root, &options.filter, options.symlinks);
+        while let Some(entry) = entries.next() {
+            let dir = match filter_dir(entry) {
+                Some(entry) => entry.path().to_path_buf(),
+                None => continue,
+            };
+            let is_new = !self.watches.contains_key(&dir);
+            match self.add_single_watch(dir.clone()) {
+                Ok(()) => {
+                    if is_new {
+                        added.push(dir);
+                    }
+                }
+                Err(err) => {
+                    match self.poll_instead(dir.clone(), err) {
+                        Ok(()) => {
+                            // the directories below are polled along with `dir`
+                            entries.skip_current_dir();
+                            added.push(dir);
+                        }
+                        Err(err) => {
+                            self.remove_added_watches(added);
+                            return Err(err);
+                        }
+                    }
+                }
+            }
         }
 
-        for entry in WalkDir::new(path).follow_links(true).into_iter().filter_map(filter_dir) {
-            try!(self.add_single_watch(entry.path().to_path_buf(), is_recursive, watch_self));
-            watch_self = false;
+        Ok(())
+    }
+
+    /// Poll `path` instead of watching it, if the watch limit was reached and the poll fallback
+    /// is enabled, otherwise returns `err`
+    fn poll_instead(&mut self, path: PathBuf, err: Error) -> Result<()> {
+        match (&err.kind, self.poll_interval) {
+            (&ErrorKind::MaxFilesWatch, Some(_)) => {}
+            _ => return Err(err),
         }
 
+        // the limit is still reported, polling is slower and misses some operations
+        self.event_tx.send(RawEvent {
+                               path: Some(path.clone()),
+                               op: Err(err),
+                               kind: None,
+                               cookie: None,
+                               pid: None,
+                           });
+
+        let (root, options) = match find_root(&self.roots, &path) {
+            Some((root, options)) => (root.clone(), options.clone()),
+            None => (path.clone(), WatchOptions::new(RecursiveMode::Recursive)),
+        };
+        if let Some(data) = WatchData::new(&path, &root, &options, None, &mut self.event_tx) {
+            self.polled.insert(path, data);
+        }
         Ok(())
     }
 
-    fn add_single_watch(&mut self,
-                        path: PathBuf,
-                        is_recursive: bool,
-                        watch_self: bool)
-                        -> Result<()> {
-        let mut flags = flags::IN_ATTRIB | flags::IN_CREATE | flags::IN_DELETE |
-                        flags::IN_CLOSE_WRITE | flags::IN_MODIFY |
-                        flags::IN_MOVED_FROM | flags::IN_MOVED_TO;
+    /// Remove the watches of directories added by an `add_watch` call that failed
+    fn remove_added_watches(&mut self, added: Vec<PathBuf>) {
+        for path in added {
+            self.polled.remove(&path);
+            self.mount_points.remove(&path);
+            if let Some((w, _, _)) = self.watches.remove(&path) {
+                self.paths.remove(&w);
+                if let Some(ref inotify) = self.inotify {
+                    let _ = inotify.rm_watch(w);
+                }
+            }
+        }
+    }
+
+    /// Set the deadline for the next scan of the polled directories, unless it's already set
+    fn schedule_poll(&mut self) {
+        if self.polled.is_empty() || self.poll_deadline.is_some() {
+            return;
+        }
+        if let Some(interval) = self.poll_interval {
+            self.poll_deadline = Some(Instant::now() + interval);
+        }
+    }
 
-        if watch_self {
-            flags.insert(flags::IN_DELETE_SELF);
-            flags.insert(flags::IN_MOVE_SELF);
+    /// Report the contents of a directory that appeared in a recursively watched directory
+    ///
+    /// Files and directories created before the watch of `dir` was installed don't produce any
+    /// inotify events, so a synthetic `CREATE` event is sent for everything found below `dir`.
+    /// Events inotify reports afterwards for the same paths are dropped as duplicates.
+    fn scan_new_directory(&mut self, dir: &Path) {
+        let (root, options) = match find_root(&self.roots, dir) {
+            Some((root, options)) => (root.clone(), options.clone()),
+            None => return,
+        };
+        if !options.ops.contains(op::CREATE) {
+            return;
+        }
+
+        let walker = WalkDir::new(dir).min_depth(1);
+        for entry in walk::walk(walker, &root, &options.filter, options.symlinks)
+            .filter_map(|e| e.ok()) {
+            let path = entry.path().to_path_buf();
+            self.synthetic_creates.insert(path.clone(), Instant::now());
+            if !options.filter.matches(path.strip_prefix(&root).unwrap()) {
+                continue;
+            }
+            let kind = if entry.file_type().is_dir() {
+                EventKind::Create(CreateKind::Folder)
+            } else {
+                EventKind::Create(CreateKind::File)
+            };
+            send_event(&mut self.event_tx,
+                       &mut self.roots,
+                       &mut self.finished,
+                       RawEvent {
+                           path: Some(path),
+                           op: Ok(kind.into()),
+                           kind: Some(kind),
+                           cookie: None,
+                           pid: None,
+                       });
+            if options.once {
+                break;
+            }
         }
+    }
 
-        if let Some(&(_, old_flags, _)) = self.watches.get(&path) {
-            flags.insert(old_flags);
-            flags.insert(flags::IN_MASK_ADD);
+    /// Watch `path` with the flags its owners need, see `watch_owners`
+    ///
+    /// The flags of an existing watch are replaced, so that they don't keep the flags of owners
+    /// that were removed.
+    fn add_single_watch(&mut self, path: PathBuf) -> Result<()> {
+        let is_dir = path.is_dir();
+        let mut flags = flags::Mask::empty();
+        let mut is_recursive = false;
+        let mut owners = 0;
+        let mut oneshot = false;
+        for (root, options) in watch_owners(&self.roots, &path) {
+            owners += 1;
+            if options.follow_path {
+                flags.insert(follow_mask(options.ops));
+                continue;
+            }
+            let recursive = is_dir && options.recursive_mode.is_recursive();
+            flags.insert(watch_mask(options.ops, recursive, *root == path));
+            is_recursive = is_recursive || recursive;
+            // the kernel can end a one-shot watch of a file itself, every event it reports for
+            // the file is delivered
+            oneshot = options.once && *root == path && options.filter.is_empty();
+        }
+        if owners == 0 {
+            return Ok(());
+        }
+        if oneshot && owners == 1 && !is_dir && !self.watches.contains_key(&path) {
+            flags.insert(flags::IN_ONESHOT);
+        }
+        if let Some((_, options)) = find_root(&self.roots, &path) {
+            if options.symlinks == SymlinkPolicy::NoFollow {
+                flags.insert(flags::IN_DONT_FOLLOW);
+            }
         }
 
         if let Some(ref inotify) = self.inotify {
             match inotify.add_watch(&path, flags.bits()) {
-                Err(e) => Err(Error::Io(e)),
+                Err(e) => {
+                    let err = if e.raw_os_error() == Some(libc::ENOSPC) {
+                        // the inotify docs say ENOSPC means the watch limit was reached
+                        Error::new(ErrorKind::MaxFilesWatch)
+                    } else {
+                        Error::io(e)
+                    };
+                    Err(err.add_path(path))
+                }
                 Ok(w) => {
-                    flags.remove(flags::IN_MASK_ADD);
+                    if is_mount_point(&path) {
+                        self.mount_points.insert(path.clone());
+                    } else {
+                        self.mount_points.remove(&path);
+                    }
                     self.watches.insert(path.clone(), (w, flags, is_recursive));
                     self.paths.insert(w, path);
                     Ok(())
@@ -321,19 +1335,64 @@ impl INotifyHandler {
         }
     }
 
+    /// Watch `path` through its parent directory, see `WatchOptions::follow_path`
+    fn follow(&mut self, path: PathBuf) -> Result<()> {
+        let dir = match path.parent() {
+            Some(dir) => dir.to_path_buf(),
+            None => {
+                return Err(Error::invalid_config("a followed path needs a parent directory")
+                    .add_path(path))
+            }
+        };
+        try!(self.add_single_watch(dir));
+        if metadata(&path).is_err() {
+            self.absent_paths.insert(path);
+        }
+        Ok(())
+    }
+
+    /// Remove the watches of `path`, which was removed or moved out of the watched directories,
+    /// and of the directories below it
     fn remove_watch(&mut self, path: PathBuf, remove_recursive: bool) -> Result<()> {
+        if self.roots.get(&path).map_or(false, |options| options.follow_path) {
+            let options = self.roots.remove(&path).unwrap();
+            return self.release_root(path, &options);
+        }
+
+        // the watches are gone before inotify reports IN_IGNORED for them, so the roots watched
+        // by them are dropped here, including followed paths in the removed directories
+        let is_recursive = remove_recursive ||
+                           self.watches.get(&path).map_or(false, |&(_, _, recursive)| recursive);
+        let dropped: Vec<PathBuf> = self.roots
+            .iter()
+            .filter(|&(root, options)| {
+                let dir = if options.follow_path { root.parent() } else { Some(root.as_path()) };
+                dir.map_or(false, |dir| dir == path || is_recursive && dir.starts_with(&path))
+            })
+            .map(|(root, _)| root.clone())
+            .collect();
+        for root in &dropped {
+            self.roots.remove(root);
+        }
+        send_lifecycle_events(Vec::new(), dropped, &mut self.event_tx);
+
+        let polled_len = self.polled.len();
+        self.polled.retain(|dir, _| !dir.starts_with(&path));
+        let was_polled = self.polled.len() < polled_len;
         match self.watches.remove(&path) {
-            None => return Err(Error::WatchNotFound),
+            None if was_polled => {}
+            None => return Err(Error::watch_not_found().add_path(path)),
             Some((w, _, is_recursive)) => {
                 if let Some(ref inotify) = self.inotify {
-                    try!(inotify.rm_watch(w).map_err(Error::Io));
+                    try!(inotify.rm_watch(w).map_err(|e| Error::io(e).add_path(path.clone())));
                     self.paths.remove(&w);
 
                     if is_recursive || remove_recursive {
                         let mut remove_list = Vec::new();
                         for (w, p) in &self.paths {
                             if p.starts_with(&path) {
-                                try!(inotify.rm_watch(*w).map_err(Error::Io));
+                                try!(inotify.rm_watch(*w)
+                                    .map_err(|e| Error::io(e).add_path(p.clone())));
                                 self.watches.remove(p);
                                 remove_list.push(*w);
                             }
@@ -351,31 +1410,48 @@ impl INotifyHandler {
     fn remove_all_watches(&mut self) -> Result<()> {
         if let Some(ref inotify) = self.inotify {
             for w in self.paths.keys() {
-                try!(inotify.rm_watch(*w).map_err(Error::Io));
+                try!(inotify.rm_watch(*w).map_err(Error::io));
             }
             self.watches.clear();
             self.paths.clear();
+            self.roots.clear();
+            self.mount_points.clear();
+            self.absent_paths.clear();
+            self.synthetic_creates.clear();
+            self.polled.clear();
         }
         Ok(())
     }
 }
 
-impl Watcher for INotifyWatcher {
-    fn new_raw(tx: Sender<RawEvent>) -> Result<INotifyWatcher> {
-        INotify::init()
-            .and_then(|inotify| EventLoop::new().map(|l| (inotify, l)))
-            .and_then(|(inotify, mut event_loop)| {
-                let inotify_fd = inotify.fd;
-                let evented_inotify = mio::unix::EventedFd(&inotify_fd);
+/// Current value of the `fs.inotify.max_user_watches` sysctl
+fn max_user_watches() -> Option<u64> {
+    fs::read_to_string("/proc/sys/fs/inotify/max_user_watches")
+        .ok()
+        .and_then(|limit| limit.trim().parse().ok())
+}
+
+/// Returns `path` relative to the current directory, unless it's absolute
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_owned())
+    } else {
+        let p = try!(env::current_dir().map_err(Error::io));
+        Ok(p.join(path))
+    }
+}
 
-                let handler = INotifyHandler {
-                    inotify: Some(inotify),
-                    event_loop_tx: event_loop.channel(),
-                    event_tx: EventTx::Raw { tx: tx },
-                    watches: HashMap::new(),
-                    paths: HashMap::new(),
-                    rename_event: None,
-                };
+impl INotifyWatcher {
+    fn from_event_tx(event_tx: EventTx, builder: &WatcherBuilder) -> Result<INotifyWatcher> {
+        let handler = try!(INotifyHandler::new(event_tx, builder));
+
+        let mut config = EventLoopConfig::new();
+        config.timer_tick_ms(TIMER_TICK_MS);
+
+        EventLoop::configured(config)
+            .and_then(|mut event_loop| {
+                let inotify_fd = handler.raw_fd();
+                let evented_inotify = mio::unix::EventedFd(&inotify_fd);
 
                 event_loop.register(&evented_inotify,
                                     INOTIFY,
@@ -386,76 +1462,58 @@ impl Watcher for INotifyWatcher {
             .map(|(mut event_loop, mut handler)| {
                 let channel = event_loop.channel();
 
+                let thread_name = builder.thread_name
+                    .clone()
+                    .unwrap_or_else(|| "INotify Watcher".to_owned());
+
                 ThreadBuilder::new()
-                    .name("INotify Watcher".to_owned())
+                    .name(thread_name)
                     .spawn(move || event_loop.run(&mut handler))
                     .unwrap();
 
                 INotifyWatcher(channel)
             })
-            .map_err(Error::Io)
+            .map_err(Error::io)
     }
+}
 
-    fn new(tx: Sender<DebouncedEvent>, delay: Duration) -> Result<INotifyWatcher> {
-        INotify::init()
-            .and_then(|inotify| EventLoop::new().map(|l| (inotify, l)))
-            .and_then(|(inotify, mut event_loop)| {
-                let inotify_fd = inotify.fd;
-                let evented_inotify = mio::unix::EventedFd(&inotify_fd);
+impl Watcher for INotifyWatcher {
+    fn new_raw<F: EventHandler<RawEvent>>(handler: F) -> Result<INotifyWatcher> {
+        INotifyWatcher::from_event_tx(EventTx::raw(handler), &WatcherBuilder::new())
+    }
 
-                let handler = INotifyHandler {
-                    inotify: Some(inotify),
-                    event_loop_tx: event_loop.channel(),
-                    event_tx: EventTx::Debounced {
-                        tx: tx.clone(),
-                        debounce: Debounce::new(delay, tx),
-                    },
-                    watches: HashMap::new(),
-                    paths: HashMap::new(),
-                    rename_event: None,
-                };
+    fn new<F: EventHandler<DebouncedEvent>>(handler: F, delay: Duration) -> Result<INotifyWatcher> {
+        INotifyWatcher::from_event_tx(EventTx::debounced(handler, delay), &WatcherBuilder::new())
+    }
 
-                event_loop.register(&evented_inotify,
-                                    INOTIFY,
-                                    mio::EventSet::readable(),
-                                    mio::PollOpt::level())
-                    .map(|_| (event_loop, handler))
-            })
-            .map(|(mut event_loop, mut handler)| {
-                let channel = event_loop.channel();
+    fn from_builder_raw<F: EventHandler<RawEvent>>(handler: F,
+                                                   builder: &WatcherBuilder)
+                                                   -> Result<INotifyWatcher> {
+        INotifyWatcher::from_event_tx(EventTx::raw(handler), builder)
+    }
 
-                ThreadBuilder::new()
-                    .name("INotify Watcher".to_owned())
-                    .spawn(move || event_loop.run(&mut handler))
-                    .unwrap();
+    fn from_builder<F: EventHandler<DebouncedEvent>>(handler: F,
+                                                     builder: &WatcherBuilder)
+                                                     -> Result<INotifyWatcher> {
+        INotifyWatcher::from_event_tx(try!(builder.debounced_tx(handler)), builder)
+    }
 
-                INotifyWatcher(channel)
-            })
-            .map_err(Error::Io)
+    fn watch(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()> {
+        self.watch_with(path, WatchOptions::new(recursive_mode))
     }
 
-    fn watch<P: AsRef<Path>>(&mut self, path: P, recursive_mode: RecursiveMode) -> Result<()> {
-        let pb = if path.as_ref().is_absolute() {
-            path.as_ref().to_owned()
-        } else {
-            let p = try!(env::current_dir().map_err(Error::Io));
-            p.join(path)
-        };
+    fn watch_with(&mut self, path: &Path, options: WatchOptions) -> Result<()> {
+        let pb = try!(absolute_path(path));
         let (tx, rx) = mpsc::channel();
-        let msg = EventLoopMsg::AddWatch(pb, recursive_mode, tx);
+        let msg = EventLoopMsg::AddWatch(pb, options, tx);
 
         // we expect the event loop to live and reply => unwraps must not panic
         self.0.send(msg).unwrap();
         rx.recv().unwrap()
     }
 
-    fn unwatch<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        let pb = if path.as_ref().is_absolute() {
-            path.as_ref().to_owned()
-        } else {
-            let p = try!(env::current_dir().map_err(Error::Io));
-            p.join(path)
-        };
+    fn unwatch(&mut self, path: &Path) -> Result<()> {
+        let pb = try!(absolute_path(path));
         let (tx, rx) = mpsc::channel();
         let msg = EventLoopMsg::RemoveWatch(pb, tx);
 
@@ -463,11 +1521,123 @@ impl Watcher for INotifyWatcher {
         self.0.send(msg).unwrap();
         rx.recv().unwrap()
     }
+
+    fn configure(&mut self, option: Config) -> Result<bool> {
+        let (tx, rx) = mpsc::channel();
+        let msg = EventLoopMsg::Configure(option, tx);
+
+        // we expect the event loop to live and reply => unwraps must not panic
+        self.0.send(msg).unwrap();
+        rx.recv().unwrap()
+    }
+
+    fn watched_paths(&self) -> Result<Vec<PathBuf>> {
+        let (tx, rx) = mpsc::channel();
+
+        // we expect the event loop to live and reply => unwraps must not panic
+        self.0.send(EventLoopMsg::WatchedPaths(tx)).unwrap();
+        Ok(rx.recv().unwrap())
+    }
+
+    fn stats(&self) -> Result<WatcherStats> {
+        let (tx, rx) = mpsc::channel();
+
+        // we expect the event loop to live and reply => unwraps must not panic
+        self.0.send(EventLoopMsg::Stats(tx)).unwrap();
+        Ok(rx.recv().unwrap())
+    }
 }
 
 impl Drop for INotifyWatcher {
     fn drop(&mut self) {
-        // we expect the event loop to live => unwrap must not panic
-        self.0.send(EventLoopMsg::Shutdown).unwrap();
+        // the event loop may be gone already if it panicked
+        let _ = self.0.send(EventLoopMsg::Shutdown);
+    }
+}
+
+impl UnthreadedINotifyWatcher {
+    /// Handle the inotify events that are available and the timers that expired
+    ///
+    /// Call this when the file descriptor returned by `as_raw_fd` is readable, and when the time
+    /// returned by `timeout` elapsed. The events are read until none are left, so the file
+    /// descriptor can also be registered edge-triggered. Never blocks.
+    pub fn process_events(&mut self) {
+        while self.0.handle_events() {}
+        self.0.handle_timers();
+    }
+
+    /// Time after which `process_events` has to be called even if the file descriptor didn't
+    /// become readable
+    ///
+    /// Returns `None` while there is nothing to wait for. Otherwise the first half of a rename is
+    /// waiting for its second half, or directories are polled because of the watch limit.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.0.next_timeout()
+    }
+}
+
+impl AsRawFd for UnthreadedINotifyWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.raw_fd()
+    }
+}
+
+impl Watcher for UnthreadedINotifyWatcher {
+    fn new_raw<F: EventHandler<RawEvent>>(handler: F) -> Result<UnthreadedINotifyWatcher> {
+        UnthreadedINotifyWatcher::from_builder_raw(handler, &WatcherBuilder::new())
+    }
+
+    fn new<F: EventHandler<DebouncedEvent>>(handler: F,
+                                            delay: Duration)
+                                            -> Result<UnthreadedINotifyWatcher> {
+        let handler = try!(INotifyHandler::new(EventTx::debounced(handler, delay),
+                                               &WatcherBuilder::new()));
+        Ok(UnthreadedINotifyWatcher(handler))
+    }
+
+    fn from_builder_raw<F: EventHandler<RawEvent>>(handler: F,
+                                                   builder: &WatcherBuilder)
+                                                   -> Result<UnthreadedINotifyWatcher> {
+        let handler = try!(INotifyHandler::new(EventTx::raw(handler), builder));
+        Ok(UnthreadedINotifyWatcher(handler))
+    }
+
+    fn from_builder<F: EventHandler<DebouncedEvent>>(handler: F,
+                                                     builder: &WatcherBuilder)
+                                                     -> Result<UnthreadedINotifyWatcher> {
+        let handler = try!(INotifyHandler::new(try!(builder.debounced_tx(handler)), builder));
+        Ok(UnthreadedINotifyWatcher(handler))
+    }
+
+    fn watch(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()> {
+        self.watch_with(path, WatchOptions::new(recursive_mode))
+    }
+
+    fn watch_with(&mut self, path: &Path, options: WatchOptions) -> Result<()> {
+        let pb = try!(absolute_path(path));
+        self.0.add_root(pb, options)
+    }
+
+    fn unwatch(&mut self, path: &Path) -> Result<()> {
+        let pb = try!(absolute_path(path));
+        self.0.remove_root(pb)
+    }
+
+    fn configure(&mut self, option: Config) -> Result<bool> {
+        Ok(self.0.configure(option))
+    }
+
+    fn watched_paths(&self) -> Result<Vec<PathBuf>> {
+        Ok(self.0.watched_paths())
+    }
+
+    fn stats(&self) -> Result<WatcherStats> {
+        Ok(self.0.stats())
+    }
+}
+
+impl Drop for UnthreadedINotifyWatcher {
+    fn drop(&mut self) {
+        self.0.shutdown();
     }
 }
@@ -23,6 +23,7 @@
 //! extern crate notify;
 //!
 //! use notify::{Watcher, RecursiveMode, watcher};
+//! use std::path::Path;
 //! use std::sync::mpsc::channel;
 //! use std::time::Duration;
 //!
@@ -36,7 +37,7 @@
 //!
 //!     // Add a path to be watched. All files and directories at that path and
 //!     // below will be monitored for changes.
-//!     watcher.watch("/home/test/notify", RecursiveMode::Recursive).unwrap();
+//!     watcher.watch(Path::new("/home/test/notify"), RecursiveMode::Recursive).unwrap();
 //!
 //!     loop {
 //!         match rx.recv() {
@@ -58,6 +59,7 @@
 //! extern crate notify;
 //!
 //! use notify::{Watcher, RecursiveMode, RawEvent, raw_watcher};
+//! use std::path::Path;
 //! use std::sync::mpsc::channel;
 //!
 //! fn main() {
@@ -70,11 +72,11 @@
 //!
 //!     // Add a path to be watched. All files and directories at that path and
 //!     // below will be monitored for changes.
-//!     watcher.watch("/home/test/notify", RecursiveMode::Recursive).unwrap();
+//!     watcher.watch(Path::new("/home/test/notify"), RecursiveMode::Recursive).unwrap();
 //!
 //!     loop {
 //!         match rx.recv() {
-//!            Ok(RawEvent{path: Some(path), op: Ok(op), cookie}) => {
+//!            Ok(RawEvent{path: Some(path), op: Ok(op), cookie, ..}) => {
 //!                println!("{:?} {:?} ({:?})", op, path, cookie)
 //!            },
 //!            Ok(event) => println!("broken event: {:?}", event),
@@ -86,7 +88,13 @@
 //!
 //! The event structure is described in the [`RawEvent`](struct.RawEvent.html) documentation,
 //! all possible operations delivered in an event are described in the [`op`](op/index.html)
-//! documentation.
+//! documentation. On Linux and with the `PollWatcher`, events also carry a more detailed
+//! [`EventKind`](event/enum.EventKind.html).
+//!
+//! ## Async streams
+//!
+//! With the `stream` feature enabled, both raw and debounced events can be consumed as a
+//! `futures::Stream`, see the [`stream`](stream/index.html) module.
 
 #![deny(missing_docs)]
 
@@ -100,8 +108,13 @@ extern crate fsevent_sys;
 extern crate winapi;
 extern crate libc;
 extern crate filetime;
+extern crate glob;
+#[cfg(feature = "stream")]
+extern crate futures;
 
 pub use self::op::Op;
+pub use self::event::EventKind;
+pub use self::filter::PathFilter;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
@@ -111,15 +124,21 @@ use std::error::Error as StdError;
 use std::result::Result as StdResult;
 use std::time::Duration;
 
+use self::debounce::EventTx;
+
 #[cfg(target_os="macos")]
 pub use self::fsevent::FsEventWatcher;
 #[cfg(target_os="linux")]
-pub use self::inotify::INotifyWatcher;
+pub use self::fanotify::FanotifyWatcher;
+#[cfg(target_os="linux")]
+pub use self::inotify::{INotifyWatcher, UnthreadedINotifyWatcher};
 #[cfg(target_os="windows")]
 pub use self::windows::ReadDirectoryChangesWatcher;
 pub use self::null::NullWatcher;
 pub use self::poll::PollWatcher;
 
+#[cfg(target_os="linux")]
+pub mod fanotify;
 #[cfg(target_os="linux")]
 pub mod inotify;
 #[cfg(target_os="macos")]
@@ -127,10 +146,15 @@ pub mod fsevent;
 #[cfg(target_os="windows")]
 pub mod windows;
 
+pub mod event;
+pub mod filter;
 pub mod null;
 pub mod poll;
+#[cfg(feature = "stream")]
+pub mod stream;
 
 mod debounce;
+mod walk;
 
 /// Contains the `Op` type which describes the actions for an event.
 ///
@@ -139,6 +163,20 @@ mod debounce;
 /// `notify`-API users will have to take care of themselves, depending on their needs.
 ///
 ///
+/// # Access, Open and Close-Nowrite
+///
+/// An `ACCESS` event is emitted whenever a file is read, an `OPEN` event whenever a file or
+/// directory is opened and a `CLOSE_NOWRITE` event whenever a file or directory that was not
+/// opened for writing has been closed.
+///
+/// These events are opt-in, they are only emitted if requested with
+/// [`WatcherBuilder::access_ops`](../struct.WatcherBuilder.html#method.access_ops). Keep in mind
+/// that listing a watched directory opens it, including when `notify` itself walks the directory
+/// to add a recursive watch.
+///
+/// __These events are only available on Linux__.
+///
+///
 /// # Chmod
 ///
 /// __Linux, OS X__
@@ -146,6 +184,15 @@ mod debounce;
 /// On Linux and OS X the `CHMOD` event is emitted whenever attributes or extended attributes
 /// change.
 ///
+/// __PollWatcher__
+///
+/// The `PollWatcher` emits a `CHMOD` event when the permissions or the owner changed between two
+/// scans, or on Unix when the status change time of a file changed without a write being
+/// detected, like inotify's `IN_ATTRIB`. A file written without changing its size and then set
+/// back to its previous modification time is reported that way too, unless its contents are
+/// compared by hash, see
+/// [`WatcherBuilder::content_hash`](../struct.WatcherBuilder.html#method.content_hash).
+///
 /// __Windows__
 ///
 /// On Windows a `WRITE` event is emitted when attributes change. This makes it impossible to
@@ -168,6 +215,12 @@ mod debounce;
 /// created before the directory could be watched, or if the directory was moved into the watched
 /// directory.
 ///
+/// __Linux__
+///
+/// With a recursive watch, the `INotifyWatcher` does this scan itself: once a directory created
+/// in or moved into a watched directory is watched, a `CREATE` event is emitted for everything it
+/// contains. Each path is reported once, even if inotify reports it as well.
+///
 /// # Remove
 ///
 /// ## Remove file or directory within a watched directory
@@ -220,6 +273,13 @@ mod debounce;
 /// A rename that originates outside of a watched directory and ends inside of a watched directory
 /// produces a `CREATE` event.
 ///
+/// __Linux__
+///
+/// When a directory is renamed within a recursively watched directory, the watches of the
+/// directory and of everything below it follow it: subsequent events contain the new paths. This
+/// includes paths passed to `watch` below the renamed directory, which have to be unwatched by
+/// their new path.
+///
 /// __OS X__
 ///
 /// A `RENAME` event is produced whenever a file or directory is moved. This includes moves within
@@ -231,6 +291,13 @@ mod debounce;
 /// or directory is moved out of the watched directory and a different file or directory is moved
 /// in.
 ///
+/// __PollWatcher__
+///
+/// On Unix the `PollWatcher` matches a path that disappeared with one that appeared during the
+/// same interval by device, inode and, if the filesystem records it, creation time, and reports
+/// them like Linux does, only the renamed directory itself is reported. Renames into or out of the
+/// watched directory, and all renames on other platforms, produce `CREATE` and `REMOVE` events.
+///
 /// ## Rename watched file or directory itself
 ///
 /// With the exception of Windows a `RENAME` event is emitted whenever the watched file or
@@ -266,7 +333,8 @@ mod debounce;
 /// __Linux, Windows__
 ///
 /// Linux and Windows will continue to watch the moved file or directory. Events will contain paths
-/// prefixed with the old path.
+/// prefixed with the old path, unless the renamed parent directory is itself watched recursively
+/// on Linux.
 ///
 /// __OS X__
 ///
@@ -291,6 +359,22 @@ mod debounce;
 /// of the paths.
 ///
 ///
+/// # Unmount and Watch-Dropped
+///
+/// __Linux__
+///
+/// An `UNMOUNT` event is emitted when the filesystem containing a watched path is unmounted. It is
+/// emitted for the mount point, if it was watched, and for every path passed to `watch` on that
+/// filesystem, but not for the other directories watched recursively.
+///
+/// A `WATCH_DROPPED` event is emitted when the kernel stops watching a path passed to `watch`,
+/// because the path was deleted or its filesystem was unmounted. The path doesn't need to be
+/// unwatched afterwards, `unwatch` returns a `WatchNotFound` error for it.
+///
+/// Both are always delivered, regardless of the operations requested in
+/// [`WatchOptions::ops`](../struct.WatchOptions.html#structfield.ops).
+///
+///
 /// # Write
 ///
 /// A `WRITE` event is emitted whenever a file has been written to.
@@ -307,19 +391,29 @@ pub mod op {
     /// Multiple actions may be delivered in a single event.
         pub flags Op: u32 {
     /// Attributes changed
-            const CHMOD       = 0b0000001,
+            const CHMOD         = 0b000000000001,
     /// Created
-            const CREATE      = 0b0000010,
+            const CREATE        = 0b000000000010,
     /// Removed
-            const REMOVE      = 0b0000100,
+            const REMOVE        = 0b000000000100,
     /// Renamed
-            const RENAME      = 0b0001000,
+            const RENAME        = 0b000000001000,
     /// Written
-            const WRITE       = 0b0010000,
+            const WRITE         = 0b000000010000,
     /// File opened for writing was closed
-            const CLOSE_WRITE = 0b0100000,
+            const CLOSE_WRITE   = 0b000000100000,
     /// Directories need to be rescanned
-            const RESCAN      = 0b1000000,
+            const RESCAN        = 0b000001000000,
+    /// Read (opt-in)
+            const ACCESS        = 0b000010000000,
+    /// Opened (opt-in)
+            const OPEN          = 0b000100000000,
+    /// File or directory not opened for writing was closed (opt-in)
+            const CLOSE_NOWRITE = 0b001000000000,
+    /// Filesystem containing the path was unmounted
+            const UNMOUNT       = 0b010000000000,
+    /// Watched path is no longer watched
+            const WATCH_DROPPED = 0b100000000000,
         }
     }
 }
@@ -345,6 +439,13 @@ pub struct RawEvent {
     /// there is an error reading from inotify.
     pub op: Result<Op>,
 
+    /// Structured description of the operation detected on that path.
+    ///
+    /// Reported by the `INotifyWatcher` and the `PollWatcher`, in which case `op` is derived from
+    /// it. `kind` is `None` for errors, for `RESCAN`, `UNMOUNT` and `WATCH_DROPPED` events and on
+    /// the other backends.
+    pub kind: Option<EventKind>,
+
     /// Unique cookie associating related events (for `RENAME` events).
     ///
     /// If two consecutive `RENAME` events share the same cookie, it means that the first event
@@ -354,6 +455,11 @@ pub struct RawEvent {
     /// For details on handling `RENAME` events with the `FsEventWatcher` have a look at the
     /// [notify::op](op/index.html) documentation.
     pub cookie: Option<u32>,
+
+    /// Process that caused the event.
+    ///
+    /// Only reported by the `FanotifyWatcher`, `None` on the other backends.
+    pub pid: Option<u32>,
 }
 
 unsafe impl Send for RawEvent {}
@@ -385,10 +491,12 @@ pub enum DebouncedEvent {
     /// `Write` events have a higher priority than `Chmod`. `Chmod` will not be emitted if it's
     /// detected before the `Write` event has been emitted.
     ///
+    /// Writes of directories, which happen whenever their entries change, are not reported.
+    ///
     /// Upon receiving a `Create` event for a directory, it is necessary to scan the newly created
     /// directory for contents. The directory can contain files or directories if those contents
     /// were created before the directory could be watched, or if the directory was moved into the
-    /// watched directory.
+    /// watched directory. The `INotifyWatcher` already does this for recursive watches.
     Write(PathBuf),
 
     /// `Chmod` is emitted when attributes have been changed and no events were detected for the
@@ -413,6 +521,14 @@ pub enum DebouncedEvent {
     ///
     ///  This event may contain a path for which the error was detected.
     Error(Error, Option<PathBuf>),
+
+    /// `Unmount` is emitted immediately after the filesystem containing a watched path has been
+    /// unmounted.
+    Unmount(PathBuf),
+
+    /// `WatchDropped` is emitted immediately after a watched path has stopped being watched
+    /// without calling `unwatch`, for example because it was deleted.
+    WatchDropped(PathBuf),
 }
 
 impl PartialEq for DebouncedEvent {
@@ -423,7 +539,9 @@ impl PartialEq for DebouncedEvent {
             (&DebouncedEvent::Create(ref a), &DebouncedEvent::Create(ref b)) |
             (&DebouncedEvent::Write(ref a), &DebouncedEvent::Write(ref b)) |
             (&DebouncedEvent::Chmod(ref a), &DebouncedEvent::Chmod(ref b)) |
-            (&DebouncedEvent::Remove(ref a), &DebouncedEvent::Remove(ref b)) => a == b,
+            (&DebouncedEvent::Remove(ref a), &DebouncedEvent::Remove(ref b)) |
+            (&DebouncedEvent::Unmount(ref a), &DebouncedEvent::Unmount(ref b)) |
+            (&DebouncedEvent::WatchDropped(ref a), &DebouncedEvent::WatchDropped(ref b)) => a == b,
             (&DebouncedEvent::Rename(ref a1, ref a2), &DebouncedEvent::Rename(ref b1, ref b2)) => {
                 (a1 == b1 && a2 == b2)
             }
@@ -433,9 +551,9 @@ impl PartialEq for DebouncedEvent {
     }
 }
 
-/// Errors generated from the `notify` crate
+/// Kinds of errors generated from the `notify` crate
 #[derive(Debug)]
-pub enum Error {
+pub enum ErrorKind {
     /// Generic error
     ///
     /// May be used in cases where a platform specific error is mapped to this type
@@ -449,18 +567,129 @@ pub enum Error {
 
     /// Attempted to remove a watch that does not exist
     WatchNotFound,
+
+    /// An invalid value was passed as runtime configuration
+    InvalidConfig(String),
+
+    /// The limit on the number of watches per user has been reached
+    ///
+    /// On Linux this is `ENOSPC` from `inotify_add_watch` and can be raised with the
+    /// `fs.inotify.max_user_watches` sysctl. See `WatcherBuilder::poll_fallback` to poll the
+    /// directories that can't be watched instead.
+    MaxFilesWatch,
+
+    /// The limit on the number of watcher instances per user has been reached, or the process
+    /// is out of file descriptors
+    ///
+    /// On Linux this is `EMFILE` from `inotify_init` or `fanotify_init`, which is returned for
+    /// both. The instance limit can be raised with the `fs.inotify.max_user_instances` sysctl, the
+    /// file descriptor limit with `ulimit -n` or `RLIMIT_NOFILE`.
+    MaxInstances,
+
+    /// The process lacks a privilege the watcher needs
+    ///
+    /// The `FanotifyWatcher` needs `CAP_SYS_ADMIN`, and `CAP_DAC_READ_SEARCH` to resolve the paths
+    /// of events. Fall back to the `INotifyWatcher` when this error is returned.
+    PermissionDenied(String),
+}
+
+/// Errors generated from the `notify` crate
+///
+/// Besides its `kind`, an error carries the paths it relates to, if any. For example an error
+/// while adding a recursive watch names the directory that could not be watched, not just the
+/// root passed to `watch`.
+#[derive(Debug)]
+pub struct Error {
+    /// Kind of the error
+    pub kind: ErrorKind,
+
+    /// Paths related to the error
+    pub paths: Vec<PathBuf>,
+}
+
+impl Error {
+    /// Create an error of the given kind, with no paths attached.
+    pub fn new(kind: ErrorKind) -> Error {
+        Error {
+            kind: kind,
+            paths: Vec::new(),
+        }
+    }
+
+    /// Create a `Generic` error.
+    pub fn generic(msg: &str) -> Error {
+        Error::new(ErrorKind::Generic(msg.to_owned()))
+    }
+
+    /// Create an `Io` error.
+    pub fn io(err: io::Error) -> Error {
+        Error::new(ErrorKind::Io(err))
+    }
+
+    /// Create a `PathNotFound` error.
+    pub fn path_not_found() -> Error {
+        Error::new(ErrorKind::PathNotFound)
+    }
+
+    /// Create a `WatchNotFound` error.
+    pub fn watch_not_found() -> Error {
+        Error::new(ErrorKind::WatchNotFound)
+    }
+
+    /// Create an `InvalidConfig` error.
+    pub fn invalid_config(msg: &str) -> Error {
+        Error::new(ErrorKind::InvalidConfig(msg.to_owned()))
+    }
+
+    /// Create a `PermissionDenied` error.
+    pub fn permission_denied(msg: &str) -> Error {
+        Error::new(ErrorKind::PermissionDenied(msg.to_owned()))
+    }
+
+    /// Attach a path to the error.
+    pub fn add_path(mut self, path: PathBuf) -> Error {
+        self.paths.push(path);
+        self
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::PathNotFound => write!(f, "No path was found."),
+            ErrorKind::WatchNotFound => write!(f, "No watch was found."),
+            ErrorKind::Generic(ref err) => write!(f, "{}", err),
+            ErrorKind::Io(ref err) => write!(f, "{}", err),
+            ErrorKind::InvalidConfig(ref err) => write!(f, "Invalid configuration: {}", err),
+            ErrorKind::MaxFilesWatch => {
+                write!(f,
+                       "The limit on the number of watches was reached. On Linux, raise \
+                        fs.inotify.max_user_watches.")
+            }
+            ErrorKind::MaxInstances => {
+                write!(f,
+                       "The limit on the number of watcher instances or the process' file \
+                        descriptor limit was reached. On Linux, raise \
+                        fs.inotify.max_user_instances or the open files limit.")
+            }
+            ErrorKind::PermissionDenied(ref err) => write!(f, "Permission denied: {}", err),
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let error = String::from(match *self {
-            Error::PathNotFound => "No path was found.",
-            Error::WatchNotFound => "No watch was found.",
-            Error::Generic(ref err) => err.as_ref(),
-            Error::Io(ref err) => err.description(),
-        });
+        try!(write!(f, "{}", self.kind));
+        if !self.paths.is_empty() {
+            try!(write!(f, " (paths: {:?})", self.paths));
+        }
+        Ok(())
+    }
+}
 
-        write!(f, "{}", error)
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::io(err)
     }
 }
 
@@ -469,24 +698,72 @@ pub type Result<T> = StdResult<T, Error>;
 
 impl StdError for Error {
     fn description(&self) -> &str {
-        match *self {
-            Error::PathNotFound => "No path was found",
-            Error::WatchNotFound => "No watch was found",
-            Error::Generic(_) => "Generic error",
-            Error::Io(_) => "I/O Error",
+        match self.kind {
+            ErrorKind::PathNotFound => "No path was found",
+            ErrorKind::WatchNotFound => "No watch was found",
+            ErrorKind::Generic(_) => "Generic error",
+            ErrorKind::Io(_) => "I/O Error",
+            ErrorKind::InvalidConfig(_) => "Invalid configuration",
+            ErrorKind::MaxFilesWatch => "Watch limit reached",
+            ErrorKind::MaxInstances => "Watcher instance or file descriptor limit reached",
+            ErrorKind::PermissionDenied(_) => "Permission denied",
         }
     }
 
-    fn cause(&self) -> Option<&StdError> {
-        match *self {
-            Error::Io(ref cause) => Some(cause),
+    fn cause(&self) -> Option<&dyn StdError> {
+        match self.kind {
+            ErrorKind::Io(ref cause) => Some(cause),
             _ => None,
         }
     }
 }
 
+/// Type that receives the events delivered by a `Watcher`
+///
+/// Every backend, as well as the debouncer, hands its events to an `EventHandler`. It is
+/// implemented for closures taking the event as their only argument and for
+/// `std::sync::mpsc::Sender`, so both of the following work:
+///
+/// ```no_run
+/// extern crate notify;
+///
+/// use notify::{Watcher, RecommendedWatcher, RawEvent};
+/// use std::sync::mpsc::channel;
+///
+/// fn main() {
+///     let (tx, rx) = channel();
+///     let with_channel: RecommendedWatcher = Watcher::new_raw(tx).unwrap();
+///
+///     let with_closure: RecommendedWatcher = Watcher::new_raw(|event: RawEvent| {
+///         println!("{:?}", event);
+///     }).unwrap();
+/// #   drop((rx, with_channel, with_closure));
+/// }
+/// ```
+///
+/// Handlers are called from the watcher's own thread, so they should return quickly. A panic in a
+/// handler is caught, the watcher keeps delivering the following events.
+pub trait EventHandler<T>: Send + 'static {
+    /// Handle an event.
+    fn handle_event(&mut self, event: T);
+}
+
+impl<T, F> EventHandler<T> for F
+    where F: FnMut(T) + Send + 'static
+{
+    fn handle_event(&mut self, event: T) {
+        (self)(event);
+    }
+}
+
+impl<T: Send + 'static> EventHandler<T> for Sender<T> {
+    fn handle_event(&mut self, event: T) {
+        let _ = self.send(event);
+    }
+}
+
 /// Indicates whether only the provided directory or its sub-directories as well should be watched
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RecursiveMode {
     /// Watch all sub-directories as well, including directories created after installing the watch
     Recursive,
@@ -504,20 +781,167 @@ impl RecursiveMode {
     }
 }
 
+/// How symbolic links below a watched directory are handled
+///
+/// Applies to the directories walked by the `INotifyWatcher` and the `PollWatcher`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Report symbolic links themselves and never descend into the directories they point to
+    ///
+    /// The `INotifyWatcher` watches a symbolic link passed to `watch` instead of its target.
+    NoFollow,
+
+    /// Follow symbolic links, walking every directory only once
+    ///
+    /// A directory reached a second time while walking a watched directory, for example through
+    /// a link to one of its parents, is skipped instead of being walked again.
+    Follow,
+
+    /// Follow symbolic links pointing below the watched path only, skipping the others
+    ///
+    /// Directories are walked only once as with `Follow`.
+    Confined,
+}
+
+/// Options for a single watch, see [`Watcher::watch_with`](trait.Watcher.html#method.watch_with)
+#[derive(Clone, Debug)]
+pub struct WatchOptions {
+    /// Whether sub-directories are watched as well
+    pub recursive_mode: RecursiveMode,
+
+    /// Paths below the watched directory to skip, see [`PathFilter`](filter/struct.PathFilter.html)
+    pub filter: PathFilter,
+
+    /// Operations to report, see [`WatchOptions::default_ops`](#method.default_ops)
+    ///
+    /// The `INotifyWatcher` only asks the kernel for the requested operations, and the
+    /// `PollWatcher` skips the checks it doesn't need. `RESCAN`, `UNMOUNT` and `WATCH_DROPPED`
+    /// are always reported.
+    pub ops: Op,
+
+    /// Track the watched file by its path instead of by the file it currently names
+    ///
+    /// Editors and deployment tools often save a file by writing a temporary file and renaming it
+    /// over the original. A regular watch follows the original file, which is gone afterwards, so
+    /// no further events are reported. With this option the `INotifyWatcher` watches the parent
+    /// directory for events about the path, and a file renamed over it is reported as a single
+    /// `WRITE` event. The path doesn't need to exist when the watch is added, it's reported with a
+    /// `CREATE` event once it appears. The `PollWatcher` reports a replaced file as written as
+    /// well and keeps watching the path while it doesn't exist.
+    pub follow_path: bool,
+
+    /// How symbolic links are handled, defaults to `SymlinkPolicy::Follow`
+    pub symlinks: SymlinkPolicy,
+
+    /// Remove the watch after the first event reported for it, see
+    /// [`Watcher::watch_once`](trait.Watcher.html#method.watch_once)
+    pub once: bool,
+}
+
+impl WatchOptions {
+    /// Create options equivalent to calling `watch` with `recursive_mode`.
+    pub fn new(recursive_mode: RecursiveMode) -> WatchOptions {
+        WatchOptions {
+            recursive_mode: recursive_mode,
+            filter: PathFilter::new(),
+            ops: WatchOptions::default_ops(),
+            follow_path: false,
+            symlinks: SymlinkPolicy::Follow,
+            once: false,
+        }
+    }
+
+    /// Operations reported by `watch`: everything but the opt-in `ACCESS`, `OPEN` and
+    /// `CLOSE_NOWRITE`.
+    pub fn default_ops() -> Op {
+        Op::all() - (op::ACCESS | op::OPEN | op::CLOSE_NOWRITE)
+    }
+}
+
+impl Default for WatchOptions {
+    fn default() -> WatchOptions {
+        WatchOptions::new(RecursiveMode::Recursive)
+    }
+}
+
+/// How the `PollWatcher` compares the contents of files, see
+/// [`WatcherBuilder::content_hash`](struct.WatcherBuilder.html#method.content_hash)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContentHash {
+    /// Only hash files up to this size in bytes, larger files are compared by their metadata
+    pub max_size: Option<u64>,
+
+    /// Only rely on the hash when the size and the modification time of a file are unchanged
+    ///
+    /// By default the hash alone decides whether a file was written, so a modification time that
+    /// changes without the contents, as on some network filesystems, isn't reported. With this
+    /// option such changes are reported as writes, and a file is only read when its size and
+    /// modification time are the same as in the previous scan, to catch the writes the metadata
+    /// doesn't show.
+    pub only_if_unchanged: bool,
+}
+
+/// What a watcher is using to watch its paths, see
+/// [`Watcher::stats`](trait.Watcher.html#method.stats)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WatcherStats {
+    /// Number of paths watched with `watch`, `watch_with` or `watch_once`.
+    pub roots: usize,
+
+    /// Number of kernel watch descriptors in use: inotify watches on the `INotifyWatcher` and
+    /// marked filesystems on the `FanotifyWatcher`.
+    pub descriptors: usize,
+
+    /// Number of files and directories scanned by the `PollWatcher`, or by the `INotifyWatcher`
+    /// for the directories it polls because the watch limit was reached.
+    pub polled_files: usize,
+
+    /// Current value of the `fs.inotify.max_user_watches` sysctl, the number of inotify watches
+    /// the user can have across all processes.
+    ///
+    /// Only reported by the `INotifyWatcher`.
+    pub max_user_watches: Option<u64>,
+}
+
 /// Type that can deliver file activity notifications
 ///
 /// Watcher is implemented per platform using the best implementation available on that platform.
 /// In addition to such event driven implementations, a polling implementation is also provided
 /// that should work on any platform.
-pub trait Watcher: Sized {
+///
+/// The trait is object safe, so watchers using different backends can be stored together as
+/// `Box<dyn Watcher>` and the backend can be picked at runtime:
+///
+/// ```no_run
+/// # use notify::{PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+/// # use std::path::Path;
+/// # use std::sync::mpsc::channel;
+/// # fn main() {
+/// let (tx, rx) = channel();
+/// let is_network_share = true;
+///
+/// let mut watcher: Box<dyn Watcher> = if is_network_share {
+///     Box::new(PollWatcher::new_raw(tx).unwrap())
+/// } else {
+///     Box::new(RecommendedWatcher::new_raw(tx).unwrap())
+/// };
+/// watcher.watch(Path::new("/mnt/share"), RecursiveMode::Recursive).unwrap();
+/// # let _ = rx;
+/// # }
+/// ```
+///
+/// The constructors are not available on trait objects. For watching paths given as anything
+/// other than a `&Path`, see [`WatcherExt`](trait.WatcherExt.html).
+pub trait Watcher {
     /// Create a new watcher in _raw_ mode.
     ///
-    /// Events will be sent using the provided `tx` immediately after they occurred.
-    fn new_raw(tx: Sender<RawEvent>) -> Result<Self>;
+    /// Events will be delivered to the provided `handler` immediately after they occurred. See
+    /// [`EventHandler`](trait.EventHandler.html) for what can be used as a handler.
+    fn new_raw<F: EventHandler<RawEvent>>(handler: F) -> Result<Self> where Self: Sized;
 
     /// Create a new _debounced_ watcher with a `delay`.
     ///
-    /// Events won't be sent immediately but after the specified delay.
+    /// Events won't be delivered to `handler` immediately but after the specified delay.
     ///
     /// # Advantages
     ///
@@ -546,7 +970,36 @@ pub trait Watcher: Sized {
     ///
     /// If a file is saved very slowly, you might receive a `Write` event even though the file is
     /// still being written to.
-    fn new(tx: Sender<DebouncedEvent>, delay: Duration) -> Result<Self>;
+    fn new<F: EventHandler<DebouncedEvent>>(handler: F, delay: Duration) -> Result<Self>
+        where Self: Sized;
+
+    /// Create a new watcher in _raw_ mode using the options of a `WatcherBuilder`.
+    ///
+    /// Options that don't apply to the backend are ignored. Prefer calling
+    /// [`WatcherBuilder::build_raw`](struct.WatcherBuilder.html#method.build_raw).
+    fn from_builder_raw<F: EventHandler<RawEvent>>(handler: F,
+                                                   builder: &WatcherBuilder)
+                                                   -> Result<Self>
+        where Self: Sized
+    {
+        let mut watcher = try!(Self::new_raw(handler));
+        try!(builder.apply(&mut watcher));
+        Ok(watcher)
+    }
+
+    /// Create a new _debounced_ watcher using the options of a `WatcherBuilder`.
+    ///
+    /// Options that don't apply to the backend are ignored. Prefer calling
+    /// [`WatcherBuilder::build`](struct.WatcherBuilder.html#method.build).
+    fn from_builder<F: EventHandler<DebouncedEvent>>(handler: F,
+                                                     builder: &WatcherBuilder)
+                                                     -> Result<Self>
+        where Self: Sized
+    {
+        let mut watcher = try!(Self::new(handler, try!(builder.required_debounce_delay())));
+        try!(builder.apply(&mut watcher));
+        Ok(watcher)
+    }
 
     /// Begin watching a new path.
     ///
@@ -556,15 +1009,396 @@ pub trait Watcher: Sized {
     ///
     /// If the `path` is a file, `recursive_mode` will be ignored and events will be delivered only
     /// for the file.
-    fn watch<P: AsRef<Path>>(&mut self, path: P, recursive_mode: RecursiveMode) -> Result<()>;
+    fn watch(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()>;
+
+    /// Begin watching a new path with per-watch options.
+    ///
+    /// The `INotifyWatcher` and the `PollWatcher` support every option. Other watchers return an
+    /// `InvalidConfig` error for options they don't support, instead of ignoring them.
+    fn watch_with(&mut self, path: &Path, options: WatchOptions) -> Result<()> {
+        if !options.filter.is_empty() {
+            return Err(Error::invalid_config("path filters are not supported by this watcher")
+                .add_path(path.to_owned()));
+        }
+        if options.ops != WatchOptions::default_ops() {
+            return Err(Error::invalid_config("operation masks are not supported by this watcher")
+                .add_path(path.to_owned()));
+        }
+        if options.follow_path {
+            return Err(Error::invalid_config("following a path is not supported by this watcher")
+                .add_path(path.to_owned()));
+        }
+        if options.symlinks != SymlinkPolicy::Follow {
+            return Err(Error::invalid_config("symbolic link policies are not supported by this \
+                                              watcher")
+                .add_path(path.to_owned()));
+        }
+        if options.once {
+            return Err(Error::invalid_config("one-shot watches are not supported by this watcher")
+                .add_path(path.to_owned()));
+        }
+        self.watch(path, options.recursive_mode)
+    }
+
+    /// Begin watching a path until the first event is reported for it.
+    ///
+    /// Only the first event is delivered, the watch is removed afterwards as if `unwatch` was
+    /// called, including the watches of sub-directories of a recursive watch. The two halves of a
+    /// rename are delivered together. Events about the watch itself, such as errors, `RESCAN` or
+    /// `WATCH_DROPPED`, don't count.
+    ///
+    /// Supported by the `INotifyWatcher` and the `PollWatcher`, which reports the first change it
+    /// finds during a scan. Other watchers return an `InvalidConfig` error.
+    fn watch_once(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()> {
+        self.watch_with(path,
+                        WatchOptions {
+                            once: true,
+                            ..WatchOptions::new(recursive_mode)
+                        })
+    }
 
     /// Stop watching a path.
     ///
+    /// Only the watch added for `path` is removed: other watched paths inside or around it keep
+    /// delivering their events, as if `path` had never been watched. On the `INotifyWatcher` the
+    /// inotify watches are shared between overlapping watched paths, and only removed once none of
+    /// them needs them anymore.
+    ///
     /// # Errors
     ///
     /// Returns an error in the case that `path` has not been watched or if removing the watch
     /// fails.
-    fn unwatch<P: AsRef<Path>>(&mut self, path: P) -> Result<()>;
+    fn unwatch(&mut self, path: &Path) -> Result<()>;
+
+    /// Change an option of a running watcher.
+    ///
+    /// Existing watches are kept. Returns `Ok(false)` if the option doesn't apply to this watcher,
+    /// for example `Config::PollInterval` on an event based backend or `Config::DebounceDelay` on
+    /// a watcher in _raw_ mode.
+    fn configure(&mut self, option: Config) -> Result<bool> {
+        let _ = option;
+        Ok(false)
+    }
+
+    /// List the paths being watched, sorted.
+    ///
+    /// These are the paths passed to `watch`, made absolute, not the directories below them that
+    /// are watched as part of a recursive watch. A one-shot watch is listed until it reported its
+    /// event.
+    ///
+    /// Supported by the `INotifyWatcher`, the `FanotifyWatcher` and the `PollWatcher`, other
+    /// watchers return a `Generic` error.
+    fn watched_paths(&self) -> Result<Vec<PathBuf>> {
+        Err(Error::generic("listing the watched paths is not supported by this watcher"))
+    }
+
+    /// Count the watched paths and the resources used to watch them.
+    ///
+    /// Supported by the `INotifyWatcher`, the `FanotifyWatcher` and the `PollWatcher`, other
+    /// watchers return a `Generic` error.
+    fn stats(&self) -> Result<WatcherStats> {
+        Err(Error::generic("statistics are not supported by this watcher"))
+    }
+}
+
+/// Generic conveniences for every `Watcher`
+///
+/// These accept any path type, such as `&str` or `PathBuf`, and are available on trait objects
+/// as well.
+pub trait WatcherExt: Watcher {
+    /// Begin watching a new path, see [`Watcher::watch`](trait.Watcher.html#tymethod.watch).
+    fn watch_path<P: AsRef<Path>>(&mut self, path: P, recursive_mode: RecursiveMode) -> Result<()> {
+        self.watch(path.as_ref(), recursive_mode)
+    }
+
+    /// Stop watching a path, see [`Watcher::unwatch`](trait.Watcher.html#tymethod.unwatch).
+    fn unwatch_path<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.unwatch(path.as_ref())
+    }
+}
+
+impl<W: Watcher + ?Sized> WatcherExt for W {}
+
+/// Watcher options that can be changed at runtime, see
+/// [`Watcher::configure`](trait.Watcher.html#method.configure).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Config {
+    /// Time the debouncer waits for further events before emitting an event.
+    ///
+    /// Only applies to _debounced_ watchers. Events that are already pending keep their deadline.
+    DebounceDelay(Duration),
+
+    /// Time between two scans of the watched paths.
+    ///
+    /// Only applies to the `PollWatcher`.
+    PollInterval(Duration),
+
+    /// Time the inotify backend waits for an `IN_MOVED_TO` event matching an `IN_MOVED_FROM`
+    /// event before reporting the latter as a `REMOVE`.
+    ///
+    /// Only applies to the `INotifyWatcher`.
+    RenameWindow(Duration),
+}
+
+/// Backend of a watcher chosen at runtime, see
+/// [`WatcherBuilder::backend`](struct.WatcherBuilder.html#method.backend)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// The `RecommendedWatcher` for the current platform.
+    Recommended,
+
+    /// The `PollWatcher`, available on every platform.
+    Poll,
+
+    /// The `INotifyWatcher`.
+    #[cfg(target_os = "linux")]
+    INotify,
+
+    /// The `FanotifyWatcher`.
+    #[cfg(target_os = "linux")]
+    Fanotify,
+
+    /// The `FsEventWatcher`.
+    #[cfg(target_os = "macos")]
+    FsEvent,
+
+    /// The `ReadDirectoryChangesWatcher`.
+    #[cfg(target_os = "windows")]
+    ReadDirectoryChanges,
+
+    /// The `NullWatcher`, which never reports any event.
+    Null,
+}
+
+/// Builder collecting the options used to create a watcher
+///
+/// With `build` and `build_raw` the backend is selected by the type the watcher is built as:
+///
+/// ```no_run
+/// extern crate notify;
+///
+/// use notify::{PollWatcher, WatcherBuilder};
+/// use std::sync::mpsc::channel;
+/// use std::time::Duration;
+///
+/// fn main() {
+///     let (tx, rx) = channel();
+///
+///     let watcher: PollWatcher = WatcherBuilder::new()
+///         .debounce_delay(Duration::from_secs(2))
+///         .poll_interval(Duration::from_secs(5))
+///         .thread_name("config poller")
+///         .build(tx)
+///         .unwrap();
+/// #   drop((rx, watcher));
+/// }
+/// ```
+///
+/// To choose the backend at runtime, set it with `backend` and use `build_boxed` or
+/// `build_boxed_raw`:
+///
+/// ```no_run
+/// # use notify::{Backend, WatcherBuilder};
+/// # use std::sync::mpsc::channel;
+/// # use std::time::Duration;
+/// # fn main() {
+/// # let (tx, rx) = channel();
+/// let is_network_share = true;
+/// let backend = if is_network_share { Backend::Poll } else { Backend::Recommended };
+///
+/// let watcher = WatcherBuilder::new()
+///     .backend(backend)
+///     .debounce_delay(Duration::from_secs(2))
+///     .build_boxed(tx)
+///     .unwrap();
+/// # drop((rx, watcher));
+/// # }
+/// ```
+///
+/// Options that don't apply to the selected backend are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct WatcherBuilder {
+    backend: Option<Backend>,
+    debounce_delay: Option<Duration>,
+    poll_interval: Option<Duration>,
+    rename_window: Option<Duration>,
+    poll_fallback: Option<Duration>,
+    thread_name: Option<String>,
+    access_ops: Option<Op>,
+    content_hash: Option<ContentHash>,
+}
+
+impl WatcherBuilder {
+    /// Create a builder with every option set to the backend's default.
+    pub fn new() -> WatcherBuilder {
+        WatcherBuilder::default()
+    }
+
+    /// Select the backend created by `build_boxed` and `build_boxed_raw`.
+    ///
+    /// Defaults to `Backend::Recommended`. Ignored by `build` and `build_raw`, which create the
+    /// backend they are called with.
+    pub fn backend(mut self, backend: Backend) -> WatcherBuilder {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Set the delay of a _debounced_ watcher, see
+    /// [`Watcher::new`](trait.Watcher.html#tymethod.new).
+    ///
+    /// This option is required when using `build`.
+    pub fn debounce_delay(mut self, delay: Duration) -> WatcherBuilder {
+        self.debounce_delay = Some(delay);
+        self
+    }
+
+    /// Set the time between two scans of the `PollWatcher`.
+    ///
+    /// Defaults to 30 seconds in _raw_ mode and to the debounce delay in _debounced_ mode.
+    pub fn poll_interval(mut self, interval: Duration) -> WatcherBuilder {
+        self.poll_interval = Some(interval);
+        self
+    }
+
+    /// Set the time the `INotifyWatcher` waits to pair the two halves of a rename.
+    ///
+    /// Defaults to 10 milliseconds.
+    pub fn rename_window(mut self, window: Duration) -> WatcherBuilder {
+        self.rename_window = Some(window);
+        self
+    }
+
+    /// Poll the directories the `INotifyWatcher` can't watch because the inotify watch limit was
+    /// reached, scanning them every `interval`.
+    ///
+    /// Each directory that is polled instead of watched is reported with an
+    /// `ErrorKind::MaxFilesWatch` error event. Without this option `watch` fails with that error
+    /// and leaves the path unwatched, and directories created later in a recursively watched
+    /// directory are reported with an error event and stay unwatched.
+    pub fn poll_fallback(mut self, interval: Duration) -> WatcherBuilder {
+        self.poll_fallback = Some(interval);
+        self
+    }
+
+    /// Report the given opt-in operations, any of `op::ACCESS`, `op::OPEN` and
+    /// `op::CLOSE_NOWRITE`.
+    ///
+    /// Other operations in `ops` are ignored. Currently honored by the `INotifyWatcher` and the
+    /// `FanotifyWatcher`, _debounced_ watchers never report these operations.
+    pub fn access_ops(mut self, ops: Op) -> WatcherBuilder {
+        self.access_ops = Some(ops);
+        self
+    }
+
+    /// Compare the contents of files by their hash, instead of trusting their modification time
+    /// and size only.
+    ///
+    /// Meant for filesystems with coarse or unreliable modification times, such as FAT, some FUSE
+    /// filesystems and network shares. Every file is read on every scan, unless its watch doesn't
+    /// report `op::WRITE`, it's larger than `ContentHash::max_size`, or its size or modification
+    /// time changed and `ContentHash::only_if_unchanged` is set. Currently honored by the
+    /// `PollWatcher`.
+    pub fn content_hash(mut self, hash: ContentHash) -> WatcherBuilder {
+        self.content_hash = Some(hash);
+        self
+    }
+
+    /// Set the name of the thread the watcher runs on.
+    ///
+    /// The timer thread of a _debounced_ watcher is named `"<name> debouncer"`. Currently honored
+    /// by the `INotifyWatcher`, the `FanotifyWatcher` and the `PollWatcher`.
+    pub fn thread_name<S: Into<String>>(mut self, name: S) -> WatcherBuilder {
+        self.thread_name = Some(name.into());
+        self
+    }
+
+    /// Create a watcher in _raw_ mode, delivering events to `handler`.
+    pub fn build_raw<W: Watcher, F: EventHandler<RawEvent>>(&self, handler: F) -> Result<W> {
+        W::from_builder_raw(handler, self)
+    }
+
+    /// Create a _debounced_ watcher, delivering events to `handler`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no debounce delay has been set.
+    pub fn build<W: Watcher, F: EventHandler<DebouncedEvent>>(&self, handler: F) -> Result<W> {
+        W::from_builder(handler, self)
+    }
+
+    /// Create a watcher of the selected `backend` in _raw_ mode, delivering events to `handler`.
+    pub fn build_boxed_raw<F: EventHandler<RawEvent>>(&self,
+                                                      handler: F)
+                                                      -> Result<Box<dyn Watcher>> {
+        match self.backend.unwrap_or(Backend::Recommended) {
+            Backend::Recommended => boxed(self.build_raw::<RecommendedWatcher, F>(handler)),
+            Backend::Poll => boxed(self.build_raw::<PollWatcher, F>(handler)),
+            #[cfg(target_os = "linux")]
+            Backend::INotify => boxed(self.build_raw::<INotifyWatcher, F>(handler)),
+            #[cfg(target_os = "linux")]
+            Backend::Fanotify => boxed(self.build_raw::<FanotifyWatcher, F>(handler)),
+            #[cfg(target_os = "macos")]
+            Backend::FsEvent => boxed(self.build_raw::<FsEventWatcher, F>(handler)),
+            #[cfg(target_os = "windows")]
+            Backend::ReadDirectoryChanges => {
+                boxed(self.build_raw::<ReadDirectoryChangesWatcher, F>(handler))
+            }
+            Backend::Null => boxed(self.build_raw::<NullWatcher, F>(handler)),
+        }
+    }
+
+    /// Create a _debounced_ watcher of the selected `backend`, delivering events to `handler`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no debounce delay has been set.
+    pub fn build_boxed<F: EventHandler<DebouncedEvent>>(&self,
+                                                        handler: F)
+                                                        -> Result<Box<dyn Watcher>> {
+        match self.backend.unwrap_or(Backend::Recommended) {
+            Backend::Recommended => boxed(self.build::<RecommendedWatcher, F>(handler)),
+            Backend::Poll => boxed(self.build::<PollWatcher, F>(handler)),
+            #[cfg(target_os = "linux")]
+            Backend::INotify => boxed(self.build::<INotifyWatcher, F>(handler)),
+            #[cfg(target_os = "linux")]
+            Backend::Fanotify => boxed(self.build::<FanotifyWatcher, F>(handler)),
+            #[cfg(target_os = "macos")]
+            Backend::FsEvent => boxed(self.build::<FsEventWatcher, F>(handler)),
+            #[cfg(target_os = "windows")]
+            Backend::ReadDirectoryChanges => {
+                boxed(self.build::<ReadDirectoryChangesWatcher, F>(handler))
+            }
+            Backend::Null => boxed(self.build::<NullWatcher, F>(handler)),
+        }
+    }
+
+    fn required_debounce_delay(&self) -> Result<Duration> {
+        self.debounce_delay
+            .ok_or_else(|| Error::invalid_config("a debounced watcher requires a debounce delay"))
+    }
+
+    /// Create the event delivery of a _debounced_ watcher, naming the debouncer's timer thread
+    /// after the watcher's thread.
+    fn debounced_tx<F: EventHandler<DebouncedEvent>>(&self, handler: F) -> Result<EventTx> {
+        let delay = try!(self.required_debounce_delay());
+        let thread_name = self.thread_name.as_ref().map(|name| format!("{} debouncer", name));
+        Ok(EventTx::debounced_named(handler, delay, thread_name))
+    }
+
+    /// Apply the runtime configurable options to a watcher created without them.
+    fn apply<W: Watcher>(&self, watcher: &mut W) -> Result<()> {
+        if let Some(interval) = self.poll_interval {
+            try!(watcher.configure(Config::PollInterval(interval)));
+        }
+        if let Some(window) = self.rename_window {
+            try!(watcher.configure(Config::RenameWindow(window)));
+        }
+        Ok(())
+    }
+}
+
+fn boxed<W: Watcher + 'static>(watcher: Result<W>) -> Result<Box<dyn Watcher>> {
+    watcher.map(|watcher| Box::new(watcher) as Box<dyn Watcher>)
 }
 
 /// The recommended `Watcher` implementation for the current platform
@@ -583,16 +1417,18 @@ pub type RecommendedWatcher = PollWatcher;
 /// Convenience method for creating the `RecommendedWatcher` for the current platform in _raw_ mode.
 ///
 /// See [`Watcher::new_raw`](trait.Watcher.html#tymethod.new_raw).
-pub fn raw_watcher(tx: Sender<RawEvent>) -> Result<RecommendedWatcher> {
-    Watcher::new_raw(tx)
+pub fn raw_watcher<F: EventHandler<RawEvent>>(handler: F) -> Result<RecommendedWatcher> {
+    Watcher::new_raw(handler)
 }
 
 /// Convenience method for creating the `RecommendedWatcher` for the current
 /// platform in default (debounced) mode.
 ///
 /// See [`Watcher::new`](trait.Watcher.html#tymethod.new).
-pub fn watcher(tx: Sender<DebouncedEvent>, delay: Duration) -> Result<RecommendedWatcher> {
-    Watcher::new(tx, delay)
+pub fn watcher<F: EventHandler<DebouncedEvent>>(handler: F,
+                                                delay: Duration)
+                                                -> Result<RecommendedWatcher> {
+    Watcher::new(handler, delay)
 }
 
 
@@ -601,9 +1437,16 @@ fn display_formatted_errors() {
     let expected = "Some error";
 
     assert_eq!(expected,
-               format!("{}", Error::Generic(String::from(expected))));
+               format!("{}", Error::generic(expected)));
 
     assert_eq!(expected,
                format!("{}",
-                       Error::Io(io::Error::new(io::ErrorKind::Other, expected))));
+                       Error::io(io::Error::new(io::ErrorKind::Other, expected))));
+}
+
+#[test]
+fn display_errors_with_paths() {
+    let err = Error::watch_not_found().add_path(PathBuf::from("/tmp/a"));
+
+    assert_eq!("No watch was found. (paths: [\"/tmp/a\"])", format!("{}", err));
 }
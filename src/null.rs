@@ -2,10 +2,10 @@
 
 #![allow(unused_variables)]
 
-use std::sync::mpsc::Sender;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use super::{RawEvent, DebouncedEvent, Result, Watcher, RecursiveMode};
+use super::{EventHandler, RawEvent, DebouncedEvent, Result, Watcher, WatcherStats, RecursiveMode,
+            WatchOptions};
 
 /// Stub `Watcher` implementation
 ///
@@ -13,19 +13,31 @@ use super::{RawEvent, DebouncedEvent, Result, Watcher, RecursiveMode};
 pub struct NullWatcher;
 
 impl Watcher for NullWatcher {
-    fn new_raw(tx: Sender<RawEvent>) -> Result<NullWatcher> {
+    fn new_raw<F: EventHandler<RawEvent>>(handler: F) -> Result<NullWatcher> {
         Ok(NullWatcher)
     }
 
-    fn new(tx: Sender<DebouncedEvent>, delay: Duration) -> Result<NullWatcher> {
+    fn new<F: EventHandler<DebouncedEvent>>(handler: F, delay: Duration) -> Result<NullWatcher> {
         Ok(NullWatcher)
     }
 
-    fn watch<P: AsRef<Path>>(&mut self, path: P, recursive_mode: RecursiveMode) -> Result<()> {
+    fn watch(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()> {
         Ok(())
     }
 
-    fn unwatch<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+    fn watch_with(&mut self, path: &Path, options: WatchOptions) -> Result<()> {
         Ok(())
     }
+
+    fn unwatch(&mut self, path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    fn watched_paths(&self) -> Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    fn stats(&self) -> Result<WatcherStats> {
+        Ok(WatcherStats::default())
+    }
 }
@@ -4,273 +4,723 @@
 //! Rust stdlib APIs and should work on all of the platforms it supports.
 
 use filetime::FileTime;
-use self::walkdir::WalkDir;
+use self::walkdir::{DirEntry, WalkDir};
 use std::collections::HashMap;
-use std::fs;
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs::{self, File, Metadata, Permissions};
+use std::hash::Hasher;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock, Mutex};
-use std::sync::mpsc::Sender;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
-use std::time::{Duration, Instant};
-use super::{Error, RawEvent, DebouncedEvent, op, Result, Watcher, RecursiveMode};
-use super::debounce::{Debounce, EventTx};
+use std::time::{Duration, Instant, SystemTime};
+use super::{Config, ContentHash, Error, EventHandler, RawEvent, DebouncedEvent, op, Op,
+            PathFilter, Result, SymlinkPolicy, Watcher, WatcherBuilder, WatcherStats, WatchOptions,
+            RecursiveMode};
+use super::debounce::EventTx;
+use super::event::{CreateKind, DataChange, EventKind, MetadataKind, ModifyKind, RemoveKind,
+                   RenameMode};
+use super::walk::{self, file_id};
 
 extern crate walkdir;
 
+/// Cookie of the last rename found by any `PollWatcher`
+static LAST_COOKIE: AtomicUsize = AtomicUsize::new(0);
+
 struct PathData {
-    mtime: u64,
+    /// Last modification time, with the precision of the filesystem
+    mtime: FileTime,
+    size: u64,
+    file_id: Option<(u64, u64)>,
+    /// Creation time, if the filesystem records it, which a renamed file keeps
+    btime: Option<SystemTime>,
+    /// Last status change time, which can't be set back like `mtime`, `None` on Windows
+    ctime: Option<(i64, i64)>,
+    permissions: Permissions,
+    /// User and group id, `None` on Windows
+    owner: Option<(u32, u32)>,
+    /// Hash of the contents, if they are compared, see `WatcherBuilder::content_hash`
+    hash: Option<u64>,
+    is_dir: bool,
     last_check: Instant,
 }
 
-struct WatchData {
-    is_recursive: bool,
-    paths: HashMap<PathBuf, PathData>,
+impl PathData {
+    fn new(metadata: &Metadata, last_check: Instant, hash: Option<u64>) -> PathData {
+        PathData {
+            mtime: FileTime::from_last_modification_time(metadata),
+            size: metadata.len(),
+            file_id: file_id(metadata),
+            btime: metadata.created().ok(),
+            ctime: change_time(metadata),
+            permissions: metadata.permissions(),
+            owner: owner(metadata),
+            hash: hash,
+            is_dir: metadata.is_dir(),
+            last_check: last_check,
+        }
+    }
+
+    /// Returns `true` if the contents of the file may have changed since `old` was recorded
+    ///
+    /// The modification time alone misses writes within its precision, which is a second or
+    /// more on some filesystems, and writes after which it was restored, for example by
+    /// `touch -r` or `rsync`, so the size is compared too. A write keeping both is only visible as
+    /// a status change, see `changed_metadata`. A file replaced by another one counts as written.
+    /// Directories are only compared by the whole seconds of their modification time, which
+    /// changes when entries are added or removed, and those entries are reported by themselves.
+    ///
+    /// If both contents were hashed, the hashes are compared according to `hashing`.
+    fn is_written(&self, old: &PathData, hashing: Option<ContentHash>) -> bool {
+        let is_replaced = self.file_id != old.file_id;
+        let is_modified = if self.is_dir {
+            self.mtime.seconds() != old.mtime.seconds()
+        } else {
+            self.mtime != old.mtime || self.size != old.size || is_replaced
+        };
+        match (self.hash, old.hash, hashing) {
+            (Some(hash), Some(old_hash), Some(hashing)) => {
+                hash != old_hash || is_replaced || hashing.only_if_unchanged && is_modified
+            }
+            _ => is_modified,
+        }
+    }
+
+    /// Returns `true` if `old` may have been renamed to the path of this one
+    ///
+    /// Filesystems reuse the inodes of deleted files, so the creation time has to match as well,
+    /// if it's recorded.
+    fn is_renamed_from(&self, old: &PathData) -> bool {
+        self.file_id.is_some() && self.file_id == old.file_id && self.is_dir == old.is_dir &&
+        (self.btime.is_none() || old.btime.is_none() || self.btime == old.btime)
+    }
+
+    /// Returns which attributes changed since `old` was recorded, if any
+    ///
+    /// Like `IN_ATTRIB` of inotify, a status change of a file that isn't a write counts as an
+    /// attribute change, for example new extended attributes, a new hard link or new times. That
+    /// includes a write keeping the size after which the modification time was restored, which
+    /// inotify reports last as an attribute change too. The status change time of directories
+    /// changes with their entries, so it isn't compared for them.
+    fn changed_metadata(&self,
+                        old: &PathData,
+                        hashing: Option<ContentHash>)
+                        -> Option<MetadataKind> {
+        if self.permissions != old.permissions {
+            Some(MetadataKind::Permissions)
+        } else if self.owner != old.owner {
+            Some(MetadataKind::Ownership)
+        } else if !self.is_dir && self.ctime != old.ctime && !self.is_written(old, hashing) {
+            Some(MetadataKind::Any)
+        } else {
+            None
+        }
+    }
 }
 
-/// Polling based `Watcher` implementation
-pub struct PollWatcher {
-    event_tx: EventTx,
-    watches: Arc<Mutex<HashMap<PathBuf, WatchData>>>,
-    open: Arc<RwLock<bool>>,
+/// Last status change time of a file, as seconds and nanoseconds
+#[cfg(unix)]
+fn change_time(metadata: &Metadata) -> Option<(i64, i64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.ctime(), metadata.ctime_nsec()))
 }
 
-impl PollWatcher {
-    /// Create a PollWatcher which polls every `delay` milliseconds
-    pub fn with_delay_ms(tx: Sender<RawEvent>, delay: u32) -> Result<PollWatcher> {
-        let mut p = PollWatcher {
-            event_tx: EventTx::Raw { tx: tx.clone() },
-            watches: Arc::new(Mutex::new(HashMap::new())),
-            open: Arc::new(RwLock::new(true)),
+#[cfg(not(unix))]
+fn change_time(_metadata: &Metadata) -> Option<(i64, i64)> {
+    None
+}
+
+/// Hash the contents of the file at `path` if `hashing` applies to it, `None` if it doesn't or
+/// the file can't be read
+///
+/// With `ContentHash::only_if_unchanged`, the file is only read if its size and modification time
+/// are the same as in `old`, the previous scan.
+fn hash_contents(path: &Path,
+                 metadata: &Metadata,
+                 hashing: Option<ContentHash>,
+                 old: Option<&PathData>)
+                 -> Option<u64> {
+    let hashing = hashing?;
+    if !metadata.is_file() || hashing.max_size.map_or(false, |max_size| metadata.len() > max_size) {
+        return None;
+    }
+    if hashing.only_if_unchanged {
+        let old = old?;
+        let mtime = FileTime::from_last_modification_time(metadata);
+        if old.size != metadata.len() || old.mtime != mtime {
+            return None;
+        }
+    }
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = [0; 8192];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => return Some(hasher.finish()),
+            Ok(n) => hasher.write(&buffer[..n]),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(_) => return None,
+        }
+    }
+}
+
+/// User and group id of a file
+#[cfg(unix)]
+fn owner(metadata: &Metadata) -> Option<(u32, u32)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.uid(), metadata.gid()))
+}
+
+#[cfg(not(unix))]
+fn owner(_metadata: &Metadata) -> Option<(u32, u32)> {
+    None
+}
+
+/// Outcome of scanning a polled path
+#[derive(PartialEq)]
+enum Scan {
+    /// The path can't be read
+    Failed,
+    /// Nothing was reported
+    Unchanged,
+    /// At least one change was reported
+    Reported,
+}
+
+/// Snapshot of a polled path
+///
+/// Also used by the `INotifyWatcher` to poll the directories it can't watch.
+pub(crate) struct WatchData {
+    root: PathBuf,
+    is_recursive: bool,
+    filter: PathFilter,
+    ops: Op,
+    follow_path: bool,
+    symlinks: SymlinkPolicy,
+    once: bool,
+    hashing: Option<ContentHash>,
+    paths: HashMap<PathBuf, PathData>,
+}
+
+impl WatchData {
+    /// Take the first snapshot of `watch`, which is the watched path `root` or a directory below
+    /// it
+    ///
+    /// The filter of `options` matches paths relative to `root`. Only `root` itself is reported
+    /// when it changes, any other directory is reported by its parent. Returns `None` if `watch`
+    /// can't be read, after sending the error, unless it's a followed path that doesn't exist
+    /// yet. The contents of files are compared according to `hashing`, if set and writes are
+    /// reported, reading them isn't needed otherwise.
+    pub(crate) fn new(watch: &Path,
+                      root: &Path,
+                      options: &WatchOptions,
+                      hashing: Option<ContentHash>,
+                      event_tx: &mut EventTx)
+                      -> Option<WatchData> {
+        let mut data = WatchData {
+            root: root.to_path_buf(),
+            is_recursive: options.recursive_mode.is_recursive(),
+            filter: options.filter.clone(),
+            ops: options.ops,
+            follow_path: options.follow_path,
+            symlinks: options.symlinks,
+            once: options.once,
+            hashing: hashing.filter(|_| options.ops.contains(op::WRITE)),
+            paths: HashMap::new(),
         };
-        let event_tx = EventTx::Raw { tx: tx };
-        p.run(Duration::from_millis(delay as u64), event_tx);
-        Ok(p)
+        match data.scan(watch, Instant::now(), None, event_tx) {
+            Scan::Failed => None,
+            _ => Some(data),
+        }
     }
 
-    fn run(&mut self, delay: Duration, mut event_tx: EventTx) {
-        let watches = self.watches.clone();
-        let open = self.open.clone();
+    /// The watched path this snapshot belongs to
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
 
-        thread::spawn(move || {
-            // In order of priority:
-            // TODO: handle chmod events
-            // TODO: handle renames
-            // TODO: DRY it up
+    /// Number of files and directories in the snapshot
+    pub(crate) fn path_count(&self) -> usize {
+        self.paths.len()
+    }
 
-            loop {
-                if !(*open.read().unwrap()) {
-                    break;
+    /// Scan `watch` again and report the changes since the previous scan
+    ///
+    /// Returns `false` if this is a one-shot watch that reported its event, and has to be
+    /// removed.
+    pub(crate) fn rescan(&mut self, watch: &Path, event_tx: &mut EventTx) -> bool {
+        if !self.ops.intersects(op::CREATE | op::REMOVE | op::WRITE | op::CHMOD | op::RENAME) {
+            // nothing this watcher can detect was requested
+            return true;
+        }
+
+        let current_time = Instant::now();
+        let mut created = Vec::new();
+        if self.scan(watch, current_time, Some(&mut created), event_tx) == Scan::Reported &&
+           self.once {
+            // the scan stopped at the first change, so the remaining paths weren't checked
+            return false;
+        }
+
+        let mut removed: Vec<PathBuf> = self.paths
+            .iter()
+            .filter(|&(_, data)| data.last_check < current_time)
+            .map(|(path, _)| path.clone())
+            .collect();
+        removed.sort();
+        let removed = removed.into_iter()
+            .map(|path| {
+                let data = self.paths.remove(&path).unwrap();
+                (path, data)
+            })
+            .collect();
+
+        !(self.report_moves(created, removed, event_tx) && self.once)
+    }
+
+    /// Report the paths that appeared and disappeared since the previous scan, returns `true` if
+    /// anything was reported
+    ///
+    /// A path that disappeared and one that appeared with the same device and inode are reported
+    /// as a rename, sharing a cookie. Like inotify, the paths inside a renamed directory are not
+    /// reported, and a renamed path is neither created nor removed, even if `RENAME` wasn't
+    /// requested. A write or an attribute change found together with a rename is reported
+    /// first, for the old path. On filesystems without creation times, a file deleted and another
+    /// one created within one interval may be paired by mistake if the inode is reused. A one-shot
+    /// watch stops at the first change it reports.
+    fn report_moves(&self,
+                    created: Vec<PathBuf>,
+                    removed: Vec<(PathBuf, PathData)>,
+                    event_tx: &mut EventTx)
+                    -> bool {
+        let mut by_id = HashMap::new();
+        for (i, path) in created.iter().enumerate() {
+            if let Some(id) = self.paths[path].file_id {
+                by_id.entry(id).or_insert(i);
+            }
+        }
+
+        let mut created: Vec<Option<PathBuf>> = created.into_iter().map(Some).collect();
+        let mut moved_dirs: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let mut unpaired = Vec::new();
+        for (from, old) in removed {
+            let index = old.file_id.and_then(|id| by_id.get(&id).cloned()).filter(|&i| {
+                created[i].as_ref().map_or(false, |to| self.paths[to].is_renamed_from(&old))
+            });
+            let to = match index.and_then(|i| created[i].take()) {
+                Some(to) => to,
+                None => {
+                    unpaired.push((from, old));
+                    continue;
                 }
+            };
 
-                if let Ok(mut watches) = watches.lock() {
-                    let current_time = Instant::now();
+            let is_inside_moved_dir = moved_dirs.iter().any(|(from_dir, to_dir)| {
+                from.strip_prefix(from_dir).map_or(false, |rel| to == to_dir.join(rel))
+            });
+            if is_inside_moved_dir {
+                continue;
+            }
+            if old.is_dir {
+                moved_dirs.push((from.clone(), to.clone()));
+            }
+
+            let new = &self.paths[&to];
+            // renaming changes the status change time by itself
+            let old = PathData { ctime: new.ctime, ..old };
+            let mut kinds = Vec::new();
+            if self.ops.contains(op::WRITE) && new.is_written(&old, self.hashing) {
+                kinds.push(EventKind::Modify(ModifyKind::Data(DataChange::Any)));
+            }
+            if self.ops.contains(op::CHMOD) {
+                if let Some(metadata_kind) = new.changed_metadata(&old, self.hashing) {
+                    kinds.push(EventKind::Modify(ModifyKind::Metadata(metadata_kind)));
+                }
+            }
+            if self.once && !kinds.is_empty() {
+                send_event(event_tx, &from, kinds[0], None);
+                return true;
+            }
+            for kind in kinds {
+                send_event(event_tx, &from, kind, None);
+            }
+            if self.ops.contains(op::RENAME) {
+                let cookie = LAST_COOKIE.fetch_add(1, Ordering::SeqCst).wrapping_add(1) as u32;
+                let kind = EventKind::Modify(ModifyKind::Name(RenameMode::From));
+                send_event(event_tx, &from, kind, Some(cookie));
+                let kind = EventKind::Modify(ModifyKind::Name(RenameMode::To));
+                send_event(event_tx, &to, kind, Some(cookie));
+                if self.once {
+                    return true;
+                }
+            }
+        }
+
+        let mut reported = false;
+        if self.ops.contains(op::CREATE) {
+            for path in created.into_iter().flatten() {
+                let kind = if self.paths[&path].is_dir {
+                    EventKind::Create(CreateKind::Folder)
+                } else {
+                    EventKind::Create(CreateKind::File)
+                };
+                send_event(event_tx, &path, kind, None);
+                if self.once {
+                    return true;
+                }
+                reported = true;
+            }
+        }
+        if self.ops.contains(op::REMOVE) {
+            for (path, data) in unpaired {
+                let kind = if data.is_dir {
+                    EventKind::Remove(RemoveKind::Folder)
+                } else {
+                    EventKind::Remove(RemoveKind::File)
+                };
+                send_event(event_tx, &path, kind, None);
+                if self.once {
+                    return true;
+                }
+                reported = true;
+            }
+        }
+        reported
+    }
+
+    /// Record the paths found at `watch`
+    ///
+    /// New paths are added to `created` to be reported by `report_moves`, nothing is reported
+    /// without it. A one-shot watch stops at the first change it reports.
+    fn scan(&mut self,
+            watch: &Path,
+            current_time: Instant,
+            mut created: Option<&mut Vec<PathBuf>>,
+            event_tx: &mut EventTx)
+            -> Scan {
+        let metadata = if self.symlinks == SymlinkPolicy::NoFollow {
+            fs::symlink_metadata(watch)
+        } else {
+            fs::metadata(watch)
+        };
+        match metadata {
+            Err(ref e) if self.follow_path && e.kind() == io::ErrorKind::NotFound => {
+                // reported as removed by `rescan` if it existed before
+                Scan::Unchanged
+            }
+            Err(e) => {
+                event_tx.send(RawEvent {
+                    path: Some(watch.to_path_buf()),
+                    op: Err(Error::io(e).add_path(watch.to_path_buf())),
+                    kind: None,
+                    cookie: None,
+                    pid: None,
+                });
+                Scan::Failed
+            }
+            Ok(metadata) => {
+                let mut scan = Scan::Unchanged;
+                if !metadata.is_dir() {
+                    let hash = hash_contents(watch, &metadata, self.hashing, self.paths.get(watch));
+                    let kinds = update(&mut self.paths,
+                                       self.ops,
+                                       self.hashing,
+                                       watch,
+                                       PathData::new(&metadata, current_time, hash),
+                                       created);
+                    if report(watch, kinds, self.once, event_tx) {
+                        scan = Scan::Reported;
+                    }
+                } else {
+                    let min_depth = if watch == self.root { 0 } else { 1 };
+                    let entries = walk(watch,
+                                       &self.root,
+                                       min_depth,
+                                       self.is_recursive,
+                                       &self.filter,
+                                       self.symlinks);
+                    for entry in entries {
+                        let path = entry.path();
 
-                    for (watch, &mut WatchData { is_recursive, ref mut paths }) in
-                        watches.iter_mut() {
-                        match fs::metadata(watch) {
+                        match entry.metadata() {
                             Err(e) => {
                                 event_tx.send(RawEvent {
-                                    path: Some(watch.clone()),
-                                    op: Err(Error::Io(e)),
+                                    path: Some(path.to_path_buf()),
+                                    op: Err(Error::io(e.into()).add_path(path.to_path_buf())),
+                                    kind: None,
                                     cookie: None,
+                                    pid: None,
                                 });
-                                continue;
                             }
-                            Ok(metadata) => {
-                                if !metadata.is_dir() {
-                                    let mtime = FileTime::from_last_modification_time(&metadata)
-                                        .seconds();
-                                    match paths.insert(watch.clone(),
-                                                       PathData {
-                                                           mtime: mtime,
-                                                           last_check: current_time,
-                                                       }) {
-                                        None => {
-                                            unreachable!();
-                                        }
-                                        Some(PathData { mtime: old_mtime, .. }) => {
-                                            if mtime > old_mtime {
-                                                event_tx.send(RawEvent {
-                                                    path: Some(watch.clone()),
-                                                    op: Ok(op::WRITE),
-                                                    cookie: None,
-                                                });
-                                            }
-                                        }
-                                    }
-                                } else {
-                                    let depth = if is_recursive { usize::max_value() } else { 1 };
-                                    for entry in WalkDir::new(watch)
-                                        .follow_links(true)
-                                        .max_depth(depth)
-                                        .into_iter()
-                                        .filter_map(|e| e.ok()) {
-                                        let path = entry.path();
-
-                                        match entry.metadata() {
-                                            Err(e) => {
-                                                event_tx.send(RawEvent {
-                                                    path: Some(path.to_path_buf()),
-                                                    op: Err(Error::Io(e.into())),
-                                                    cookie: None,
-                                                });
-                                            }
-                                            Ok(m) => {
-                                                let mtime =
-                                                    FileTime::from_last_modification_time(&m)
-                                                        .seconds();
-                                                match paths.insert(path.to_path_buf(),
-                                                                   PathData {
-                                                                       mtime: mtime,
-                                                                       last_check: current_time,
-                                                                   }) {
-                                                    None => {
-                                                        event_tx.send(RawEvent {
-                                                            path: Some(path.to_path_buf()),
-                                                            op: Ok(op::CREATE),
-                                                            cookie: None,
-                                                        });
-                                                    }
-                                                    Some(PathData { mtime: old_mtime, .. }) => {
-                                                        if mtime > old_mtime {
-                                                            event_tx.send(RawEvent {
-                                                                path: Some(path.to_path_buf()),
-                                                                op: Ok(op::WRITE),
-                                                                cookie: None,
-                                                            });
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
+                            Ok(m) => {
+                                let old = self.paths.get(path);
+                                let hash = hash_contents(path, &m, self.hashing, old);
+                                let data = PathData::new(&m, current_time, hash);
+                                let mut kinds = update(&mut self.paths, self.ops, self.hashing,
+                                                       path, data, created.as_deref_mut());
+                                if self.once && path == self.root {
+                                    // the watched directory changes along with its entries, it
+                                    // doesn't end the watch before they're reported
+                                    kinds.retain(|&kind| {
+                                        kind != EventKind::Modify(ModifyKind::Data(DataChange::Any))
+                                    });
+                                }
+                                if report(path, kinds, self.once, event_tx) {
+                                    scan = Scan::Reported;
+                                    if self.once {
+                                        break;
                                     }
                                 }
                             }
                         }
                     }
+                }
+                scan
+            }
+        }
+    }
+}
 
-                    for (_, &mut WatchData { ref mut paths, .. }) in watches.iter_mut() {
-                        let mut removed = Vec::new();
-                        for (path, &PathData { last_check, .. }) in paths.iter() {
-                            if last_check < current_time {
-                                event_tx.send(RawEvent {
-                                    path: Some(path.clone()),
-                                    op: Ok(op::REMOVE),
-                                    cookie: None,
-                                });
-                                removed.push(path.clone());
-                            }
-                        }
-                        for path in removed {
-                            (*paths).remove(&path);
-                        }
-                    }
-
-                    thread::sleep(delay);
+/// Record the current state of `path`, returns the changes to report if it was written or its
+/// attributes changed since the previous scan
+///
+/// A file replaced by another one, for example by renaming a new version over it, counts as
+/// written, see `PathData::is_written`. A new path is added to `created` instead. Nothing is
+/// reported without `created`, when taking the first snapshot.
+fn update(paths: &mut HashMap<PathBuf, PathData>,
+          ops: Op,
+          hashing: Option<ContentHash>,
+          path: &Path,
+          data: PathData,
+          created: Option<&mut Vec<PathBuf>>)
+          -> Vec<EventKind> {
+    let mut kinds = Vec::new();
+    match paths.get(path) {
+        None => {
+            if let Some(created) = created {
+                created.push(path.to_path_buf());
+            }
+            paths.insert(path.to_path_buf(), data);
+            return kinds;
+        }
+        Some(old) => {
+            if ops.contains(op::WRITE) && data.is_written(old, hashing) {
+                kinds.push(EventKind::Modify(ModifyKind::Data(DataChange::Any)));
+            }
+            if ops.contains(op::CHMOD) {
+                if let Some(metadata_kind) = data.changed_metadata(old, hashing) {
+                    kinds.push(EventKind::Modify(ModifyKind::Metadata(metadata_kind)));
                 }
             }
-        });
+        }
+    }
+    paths.insert(path.to_path_buf(), data);
+    if created.is_none() {
+        kinds.clear();
     }
+    kinds
 }
 
-impl Watcher for PollWatcher {
-    fn new_raw(tx: Sender<RawEvent>) -> Result<PollWatcher> {
-        PollWatcher::with_delay_ms(tx, 30_000)
+/// Report the changes of `path`, returns `true` if there were any
+///
+/// A write and an attribute change found by the same scan are both reported, unless `once` is
+/// set.
+fn report(path: &Path, mut kinds: Vec<EventKind>, once: bool, event_tx: &mut EventTx) -> bool {
+    if kinds.is_empty() {
+        return false;
+    }
+    if once {
+        kinds.truncate(1);
+    }
+    for kind in kinds {
+        send_event(event_tx, path, kind, None);
+    }
+    true
+}
+
+fn send_event(event_tx: &mut EventTx, path: &Path, kind: EventKind, cookie: Option<u32>) {
+    event_tx.send(RawEvent {
+        path: Some(path.to_path_buf()),
+        op: Ok(kind.into()),
+        kind: Some(kind),
+        cookie: cookie,
+        pid: None,
+    });
+}
+
+/// Walk a watched directory, without descending into excluded directories and skipping the paths
+/// whose events are not delivered
+fn walk<'a>(watch: &'a Path,
+            root: &'a Path,
+            min_depth: usize,
+            is_recursive: bool,
+            filter: &'a PathFilter,
+            symlinks: SymlinkPolicy)
+            -> impl Iterator<Item = DirEntry> + 'a {
+    let depth = if is_recursive { usize::max_value() } else { 1 };
+    let walker = WalkDir::new(watch).min_depth(min_depth).max_depth(depth);
+    walk::walk(walker, root, filter, symlinks)
+        .filter_map(|e| e.ok())
+        .filter(move |e| filter.matches(e.path().strip_prefix(root).unwrap()))
+}
+
+/// Polling based `Watcher` implementation
+pub struct PollWatcher {
+    event_tx: Arc<Mutex<EventTx>>,
+    watches: Arc<Mutex<HashMap<PathBuf, WatchData>>>,
+    open: Arc<RwLock<bool>>,
+    interval: Arc<RwLock<Duration>>,
+    hashing: Option<ContentHash>,
+    thread: Option<thread::Thread>,
+}
+
+impl PollWatcher {
+    /// Create a PollWatcher which polls every `delay` milliseconds
+    pub fn with_delay_ms<F: EventHandler<RawEvent>>(handler: F,
+                                                    delay: u32)
+                                                    -> Result<PollWatcher> {
+        let builder = WatcherBuilder::new().poll_interval(Duration::from_millis(delay as u64));
+        PollWatcher::from_builder_raw(handler, &builder)
     }
 
-    fn new(tx: Sender<DebouncedEvent>, delay: Duration) -> Result<PollWatcher> {
+    fn from_event_tx(event_tx: EventTx,
+                     interval: Duration,
+                     builder: &WatcherBuilder)
+                     -> Result<PollWatcher> {
         let mut p = PollWatcher {
-            event_tx: EventTx::DebouncedTx { tx: tx.clone() },
+            event_tx: Arc::new(Mutex::new(event_tx)),
             watches: Arc::new(Mutex::new(HashMap::new())),
             open: Arc::new(RwLock::new(true)),
+            interval: Arc::new(RwLock::new(interval)),
+            hashing: builder.content_hash,
+            thread: None,
         };
-        let event_tx = EventTx::Debounced {
-            tx: tx.clone(),
-            debounce: Debounce::new(delay, tx),
-        };
-        p.run(delay, event_tx);
+        let thread_name = builder.thread_name.clone().unwrap_or_else(|| "Poll Watcher".to_owned());
+        try!(p.run(thread_name));
         Ok(p)
     }
 
-    fn watch<P: AsRef<Path>>(&mut self, path: P, recursive_mode: RecursiveMode) -> Result<()> {
-        if let Ok(mut watches) = self.watches.lock() {
-            let current_time = Instant::now();
-
-            let watch = path.as_ref().to_owned();
+    fn run(&mut self, thread_name: String) -> Result<()> {
+        let watches = self.watches.clone();
+        let event_tx = self.event_tx.clone();
+        let open = self.open.clone();
+        let interval = self.interval.clone();
 
-            match fs::metadata(path) {
-                Err(e) => {
-                    self.event_tx.send(RawEvent {
-                        path: Some(watch.clone()),
-                        op: Err(Error::Io(e)),
-                        cookie: None,
-                    });
+        let spawned = thread::Builder::new().name(thread_name).spawn(move || {
+            loop {
+                if !(*open.read().unwrap()) {
+                    break;
                 }
-                Ok(metadata) => {
-                    if !metadata.is_dir() {
-                        let mut paths = HashMap::new();
-                        let mtime = FileTime::from_last_modification_time(&metadata).seconds();
-                        paths.insert(watch.clone(),
-                                     PathData {
-                                         mtime: mtime,
-                                         last_check: current_time,
-                                     });
-                        watches.insert(watch,
-                                       WatchData {
-                                           is_recursive: recursive_mode.is_recursive(),
-                                           paths: paths,
-                                       });
-                    } else {
-                        let mut paths = HashMap::new();
-                        let depth = if recursive_mode.is_recursive() {
-                            usize::max_value()
-                        } else {
-                            1
-                        };
-                        for entry in WalkDir::new(watch.clone())
-                            .follow_links(true)
-                            .max_depth(depth)
-                            .into_iter()
-                            .filter_map(|e| e.ok()) {
-                            let path = entry.path();
-
-                            match entry.metadata() {
-                                Err(e) => {
-                                    self.event_tx.send(RawEvent {
-                                        path: Some(path.to_path_buf()),
-                                        op: Err(Error::Io(e.into())),
-                                        cookie: None,
-                                    });
-                                }
-                                Ok(m) => {
-                                    let mtime = FileTime::from_last_modification_time(&m).seconds();
-                                    paths.insert(path.to_path_buf(),
-                                                 PathData {
-                                                     mtime: mtime,
-                                                     last_check: current_time,
-                                                 });
-                                }
-                            }
-                        }
-                        watches.insert(watch,
-                                       WatchData {
-                                           is_recursive: recursive_mode.is_recursive(),
-                                           paths: paths,
-                                       });
-                    }
+
+                if let (Ok(mut watches), Ok(mut event_tx)) = (watches.lock(), event_tx.lock()) {
+                    // one-shot watches are removed once they reported their event
+                    watches.retain(|watch, data| data.rescan(watch, &mut event_tx));
                 }
+
+                // the locks are released before sleeping so that `watch` and `configure` don't
+                // have to wait for the next scan; `configure` unparks the thread to apply a new
+                // interval immediately
+                let delay = *interval.read().unwrap();
+                thread::park_timeout(delay);
+            }
+        });
+        let handle = try!(spawned.map_err(Error::io));
+        self.thread = Some(handle.thread().clone());
+        Ok(())
+    }
+}
+
+/// Returns `path` relative to the current directory, unless it's absolute
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_owned())
+    } else {
+        let p = try!(env::current_dir().map_err(Error::io));
+        Ok(p.join(path))
+    }
+}
+
+impl Watcher for PollWatcher {
+    fn new_raw<F: EventHandler<RawEvent>>(handler: F) -> Result<PollWatcher> {
+        PollWatcher::from_builder_raw(handler, &WatcherBuilder::new())
+    }
+
+    fn new<F: EventHandler<DebouncedEvent>>(handler: F, delay: Duration) -> Result<PollWatcher> {
+        PollWatcher::from_builder(handler, &WatcherBuilder::new().debounce_delay(delay))
+    }
+
+    fn from_builder_raw<F: EventHandler<RawEvent>>(handler: F,
+                                                   builder: &WatcherBuilder)
+                                                   -> Result<PollWatcher> {
+        let interval = builder.poll_interval.unwrap_or(Duration::from_secs(30));
+        PollWatcher::from_event_tx(EventTx::raw(handler), interval, builder)
+    }
+
+    fn from_builder<F: EventHandler<DebouncedEvent>>(handler: F,
+                                                     builder: &WatcherBuilder)
+                                                     -> Result<PollWatcher> {
+        let delay = try!(builder.required_debounce_delay());
+        let interval = builder.poll_interval.unwrap_or(delay);
+        PollWatcher::from_event_tx(try!(builder.debounced_tx(handler)), interval, builder)
+    }
+
+    fn watch(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()> {
+        self.watch_with(path, WatchOptions::new(recursive_mode))
+    }
+
+    fn watch_with(&mut self, path: &Path, options: WatchOptions) -> Result<()> {
+        let pb = try!(absolute_path(path));
+        if let (Ok(mut watches), Ok(mut event_tx)) = (self.watches.lock(), self.event_tx.lock()) {
+            if let Some(data) = WatchData::new(&pb, &pb, &options, self.hashing, &mut event_tx) {
+                watches.insert(pb, data);
             }
         }
         Ok(())
     }
 
-    fn unwatch<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        if (*self.watches).lock().unwrap().remove(path.as_ref()).is_some() {
+    fn unwatch(&mut self, path: &Path) -> Result<()> {
+        let pb = try!(absolute_path(path));
+        if (*self.watches).lock().unwrap().remove(&pb).is_some() {
             Ok(())
         } else {
-            Err(Error::WatchNotFound)
+            Err(Error::watch_not_found().add_path(pb))
         }
     }
+
+    fn configure(&mut self, option: Config) -> Result<bool> {
+        match option {
+            Config::PollInterval(interval) => {
+                *self.interval.write().unwrap() = interval;
+                if let Some(ref thread) = self.thread {
+                    thread.unpark();
+                }
+                Ok(true)
+            }
+            Config::DebounceDelay(delay) => Ok(self.event_tx.lock().unwrap().set_delay(delay)),
+            Config::RenameWindow(_) => Ok(false),
+        }
+    }
+
+    fn watched_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths: Vec<PathBuf> = (*self.watches).lock().unwrap().keys().cloned().collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn stats(&self) -> Result<WatcherStats> {
+        let watches = (*self.watches).lock().unwrap();
+        Ok(WatcherStats {
+            roots: watches.len(),
+            descriptors: 0,
+            polled_files: watches.values().map(WatchData::path_count).sum(),
+            max_user_watches: None,
+        })
+    }
 }
 
 impl Drop for PollWatcher {
@@ -279,5 +729,8 @@ impl Drop for PollWatcher {
             let mut open = (*self.open).write().unwrap();
             (*open) = false;
         }
+        if let Some(ref thread) = self.thread {
+            thread.unpark();
+        }
     }
 }
@@ -0,0 +1,88 @@
+//! `futures::Stream` adapters for raw and debounced events
+//!
+//! This module is only available with the `stream` feature enabled. It lets async code consume
+//! events directly instead of bridging a `std::sync::mpsc::Receiver` through a blocking thread.
+//!
+//! ```no_run
+//! extern crate futures;
+//! extern crate notify;
+//!
+//! use futures::executor::block_on_stream;
+//! use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+//! use std::path::Path;
+//! use std::time::Duration;
+//!
+//! fn main() {
+//!     let (mut watcher, events) = notify::stream::watcher::<RecommendedWatcher>(
+//!         Duration::from_secs(2)).unwrap();
+//!     watcher.watch(Path::new("/home/test/notify"), RecursiveMode::Recursive).unwrap();
+//!
+//!     for event in block_on_stream(events) {
+//!         println!("{:?}", event);
+//!     }
+//! }
+//! ```
+//!
+//! The stream wakes its task as soon as the watcher delivers an event, so it can be polled by any
+//! executor.
+
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::stream::Stream;
+use futures::task::{Context, Poll};
+use std::pin::Pin;
+use std::time::Duration;
+use super::{EventHandler, RawEvent, DebouncedEvent, Result, Watcher};
+
+/// `Stream` of events delivered by a watcher
+///
+/// The stream ends once every `StreamHandler` feeding it has been dropped, which happens when the
+/// watcher owning the handler shuts down. A debounced stream first delivers the events that were
+/// still waiting for their delay to pass.
+pub struct EventStream<T> {
+    rx: UnboundedReceiver<T>,
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+}
+
+/// `EventHandler` feeding an `EventStream`
+pub struct StreamHandler<T> {
+    tx: UnboundedSender<T>,
+}
+
+impl<T: Send + 'static> EventHandler<T> for StreamHandler<T> {
+    fn handle_event(&mut self, event: T) {
+        // the stream may have been dropped, in which case the event is discarded
+        let _ = self.tx.unbounded_send(event);
+    }
+}
+
+/// Create a connected `StreamHandler` and `EventStream` pair.
+///
+/// Use this with constructors that aren't covered by `raw_watcher` and `watcher`, for example
+/// `PollWatcher::with_delay_ms`.
+pub fn channel<T>() -> (StreamHandler<T>, EventStream<T>) {
+    let (tx, rx) = mpsc::unbounded();
+    (StreamHandler { tx: tx }, EventStream { rx: rx })
+}
+
+/// Create a watcher in _raw_ mode together with a stream of its events.
+///
+/// See [`Watcher::new_raw`](../trait.Watcher.html#tymethod.new_raw).
+pub fn raw_watcher<W: Watcher>() -> Result<(W, EventStream<RawEvent>)> {
+    let (handler, stream) = channel();
+    W::new_raw(handler).map(|watcher| (watcher, stream))
+}
+
+/// Create a _debounced_ watcher together with a stream of its events.
+///
+/// See [`Watcher::new`](../trait.Watcher.html#tymethod.new).
+pub fn watcher<W: Watcher>(delay: Duration) -> Result<(W, EventStream<DebouncedEvent>)> {
+    let (handler, stream) = channel();
+    W::new(handler, delay).map(|watcher| (watcher, stream))
+}
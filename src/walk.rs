@@ -0,0 +1,70 @@
+//! Directory walking shared by the backends which watch or scan directory trees themselves
+
+extern crate walkdir;
+
+use self::walkdir::{DirEntry, Iter, IterFilterEntry, WalkDir, WalkDirIterator};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use super::{PathFilter, SymlinkPolicy};
+
+/// Walk the directory of `walker`, which is the watched path `root` or a directory below it
+///
+/// Excluded directories and the symbolic links `symlinks` doesn't allow to follow are skipped
+/// without descending into them, and a directory reached a second time through a symbolic link is
+/// skipped as well. `filter` matches paths relative to `root`.
+pub fn walk<'a>(walker: WalkDir,
+                root: &'a Path,
+                filter: &'a PathFilter,
+                symlinks: SymlinkPolicy)
+                -> IterFilterEntry<Iter, impl FnMut(&DirEntry) -> bool + 'a> {
+    let canonical_root = match symlinks {
+        SymlinkPolicy::Confined => fs::canonicalize(root).ok(),
+        _ => None,
+    };
+    let mut visited = HashSet::new();
+
+    walker.follow_links(symlinks != SymlinkPolicy::NoFollow)
+        .into_iter()
+        .filter_entry(move |e| {
+            if let Ok(relative) = e.path().strip_prefix(root) {
+                if filter.is_excluded(relative) {
+                    return false;
+                }
+            }
+            if symlinks == SymlinkPolicy::NoFollow {
+                return true;
+            }
+            if e.path_is_symbolic_link() {
+                if let Some(ref canonical_root) = canonical_root {
+                    if !is_below(e.path(), canonical_root) {
+                        return false;
+                    }
+                }
+            }
+            if e.file_type().is_dir() {
+                if let Some(id) = e.metadata().ok().and_then(|m| file_id(&m)) {
+                    return visited.insert(id);
+                }
+            }
+            true
+        })
+}
+
+/// Returns `true` if `path` resolves to `canonical_root` or a path below it
+fn is_below(path: &Path, canonical_root: &Path) -> bool {
+    fs::canonicalize(path).map(|p| p.starts_with(canonical_root)).unwrap_or(false)
+}
+
+/// Identifies the file or directory a path names, to notice when it's replaced by another one or
+/// reached through several paths
+#[cfg(unix)]
+pub fn file_id(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+pub fn file_id(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
@@ -24,8 +24,9 @@ use std::sync::{Arc, Mutex};
 use std::sync::mpsc::{channel, Sender, Receiver};
 use std::thread;
 use std::time::Duration;
-use super::{RawEvent, DebouncedEvent, Error, op, Op, Result, Watcher, RecursiveMode};
-use super::debounce::{Debounce, EventTx};
+use super::{Config, EventHandler, RawEvent, DebouncedEvent, Error, op, Op, Result, Watcher,
+            RecursiveMode};
+use super::debounce::EventTx;
 
 const BUF_SIZE: u32 = 16384;
 
@@ -72,7 +73,7 @@ struct ReadDirectoryChangesServer {
 }
 
 impl ReadDirectoryChangesServer {
-    fn start(event_tx: EventTx,
+    fn start(event_tx: Arc<Mutex<EventTx>>,
              meta_tx: Sender<MetaEvent>,
              cmd_tx: Sender<Result<PathBuf>>,
              wakeup_sem: HANDLE)
@@ -85,7 +86,7 @@ impl ReadDirectoryChangesServer {
             let wakeup_sem = sem_temp as HANDLE;
             let server = ReadDirectoryChangesServer {
                 rx: action_rx,
-                event_tx: Arc::new(Mutex::new(event_tx)),
+                event_tx: event_tx,
                 meta_tx: meta_tx,
                 cmd_tx: cmd_tx,
                 watches: HashMap::new(),
@@ -140,8 +141,8 @@ impl ReadDirectoryChangesServer {
     fn add_watch(&mut self, path: PathBuf, is_recursive: bool) -> Result<PathBuf> {
         // path must exist and be either a file or directory
         if !path.is_dir() && !path.is_file() {
-            return Err(Error::Generic("Input watch path is neither a file nor a directory."
-                .to_owned()));
+            return Err(Error::generic("Input watch path is neither a file nor a directory.")
+                .add_path(path));
         }
 
         let (watching_file, dir_target) = {
@@ -168,12 +169,12 @@ impl ReadDirectoryChangesServer {
 
             if handle == INVALID_HANDLE_VALUE {
                 let err = if watching_file {
-                    Err(Error::Generic("You attempted to watch a single file, but parent \
-                                        directory could not be opened."
-                        .to_owned()))
+                    Err(Error::generic("You attempted to watch a single file, but parent \
+                                        directory could not be opened.")
+                        .add_path(path))
                 } else {
                     // TODO: Call GetLastError for better error info?
-                    Err(Error::PathNotFound)
+                    Err(Error::path_not_found().add_path(path))
                 };
                 return err;
             }
@@ -190,7 +191,7 @@ impl ReadDirectoryChangesServer {
             unsafe {
                 kernel32::CloseHandle(handle);
             }
-            return Err(Error::Generic("Failed to create semaphore for watch.".to_owned()));
+            return Err(Error::generic("Failed to create semaphore for watch."));
         }
         let rd = ReadData {
             dir: dir_target,
@@ -286,7 +287,9 @@ fn send_pending_rename_event(event: Option<RawEvent>, event_tx: &mut EventTx) {
         event_tx.send(RawEvent {
             path: e.path,
             op: Ok(op::REMOVE),
+            kind: None,
             cookie: None,
+            pid: None,
         });
     }
 }
@@ -337,7 +340,9 @@ unsafe extern "system" fn handle_event(error_code: u32,
                         event_tx.send(RawEvent {
                             path: Some(path),
                             op: Ok(op::RENAME),
+                            kind: None,
                             cookie: None,
+                            pid: None,
                         });
                         rename_event = None;
                     } else {
@@ -345,7 +350,9 @@ unsafe extern "system" fn handle_event(error_code: u32,
                         rename_event = Some(RawEvent {
                             path: Some(path),
                             op: Ok(op::RENAME),
+                            kind: None,
                             cookie: Some(COOKIE_COUNTER),
+                            pid: None,
                         });
                     }
                 } else {
@@ -379,7 +386,9 @@ unsafe extern "system" fn handle_event(error_code: u32,
                     event_tx.send(RawEvent {
                         path: Some(path),
                         op: Ok(o),
+                        kind: None,
                         cookie: c,
+                        pid: None,
                     });
                 }
             }
@@ -399,54 +408,60 @@ pub struct ReadDirectoryChangesWatcher {
     tx: Sender<Action>,
     cmd_rx: Receiver<Result<PathBuf>>,
     wakeup_sem: HANDLE,
+    event_tx: Arc<Mutex<EventTx>>,
 }
 
 impl ReadDirectoryChangesWatcher {
-    pub fn create(tx: Sender<RawEvent>,
-                  meta_tx: Sender<MetaEvent>)
-                  -> Result<ReadDirectoryChangesWatcher> {
+    pub fn create<F: EventHandler<RawEvent>>(handler: F,
+                                             meta_tx: Sender<MetaEvent>)
+                                             -> Result<ReadDirectoryChangesWatcher> {
         let (cmd_tx, cmd_rx) = channel();
 
         let wakeup_sem =
             unsafe { kernel32::CreateSemaphoreW(ptr::null_mut(), 0, 1, ptr::null_mut()) };
         if wakeup_sem == ptr::null_mut() || wakeup_sem == INVALID_HANDLE_VALUE {
-            return Err(Error::Generic("Failed to create wakeup semaphore.".to_owned()));
+            return Err(Error::generic("Failed to create wakeup semaphore."));
         }
 
-        let event_tx = EventTx::Raw { tx: tx };
+        let event_tx = Arc::new(Mutex::new(EventTx::raw(handler)));
 
-        let action_tx = ReadDirectoryChangesServer::start(event_tx, meta_tx, cmd_tx, wakeup_sem);
+        let action_tx = ReadDirectoryChangesServer::start(event_tx.clone(),
+                                                          meta_tx,
+                                                          cmd_tx,
+                                                          wakeup_sem);
 
         Ok(ReadDirectoryChangesWatcher {
             tx: action_tx,
             cmd_rx: cmd_rx,
             wakeup_sem: wakeup_sem,
+            event_tx: event_tx,
         })
     }
 
-    pub fn create_debounced(tx: Sender<DebouncedEvent>,
-                            meta_tx: Sender<MetaEvent>,
-                            delay: Duration)
-                            -> Result<ReadDirectoryChangesWatcher> {
+    pub fn create_debounced<F: EventHandler<DebouncedEvent>>(handler: F,
+                                                             meta_tx: Sender<MetaEvent>,
+                                                             delay: Duration)
+                                                             -> Result<ReadDirectoryChangesWatcher> {
         let (cmd_tx, cmd_rx) = channel();
 
         let wakeup_sem =
             unsafe { kernel32::CreateSemaphoreW(ptr::null_mut(), 0, 1, ptr::null_mut()) };
         if wakeup_sem == ptr::null_mut() || wakeup_sem == INVALID_HANDLE_VALUE {
-            return Err(Error::Generic("Failed to create wakeup semaphore.".to_owned()));
+            return Err(Error::generic("Failed to create wakeup semaphore."));
         }
 
-        let event_tx = EventTx::Debounced {
-            tx: tx.clone(),
-            debounce: Debounce::new(delay, tx),
-        };
+        let event_tx = Arc::new(Mutex::new(EventTx::debounced(handler, delay)));
 
-        let action_tx = ReadDirectoryChangesServer::start(event_tx, meta_tx, cmd_tx, wakeup_sem);
+        let action_tx = ReadDirectoryChangesServer::start(event_tx.clone(),
+                                                          meta_tx,
+                                                          cmd_tx,
+                                                          wakeup_sem);
 
         Ok(ReadDirectoryChangesWatcher {
             tx: action_tx,
             cmd_rx: cmd_rx,
             wakeup_sem: wakeup_sem,
+            event_tx: event_tx,
         })
     }
 
@@ -461,24 +476,24 @@ impl ReadDirectoryChangesWatcher {
 
     fn send_action_require_ack(&mut self, action: Action, pb: &PathBuf) -> Result<()> {
         match self.tx.send(action) {
-            Err(_) => Err(Error::Generic("Error sending to internal channel".to_owned())),
+            Err(_) => Err(Error::generic("Error sending to internal channel")),
             Ok(_) => {
                 // wake 'em up, we don't want to wait around for the ack
                 self.wakeup_server();
 
                 match self.cmd_rx.recv() {
                     Err(_) => {
-                        Err(Error::Generic("Error receiving from command channel".to_owned()))
+                        Err(Error::generic("Error receiving from command channel"))
                     }
                     Ok(ack_res) => {
                         match ack_res {
-                            Err(e) => Err(Error::Generic(format!("Error in watcher: {:?}", e))),
+                            Err(e) => Err(Error::generic(&format!("Error in watcher: {:?}", e))),
                             Ok(ack_pb) => {
                                 if pb.as_path() != ack_pb.as_path() {
-                                    Err(Error::Generic(format!("Expected ack for {:?} but got \
-                                                                ack for {:?}",
-                                                               pb,
-                                                               ack_pb)))
+                                    Err(Error::generic(&format!("Expected ack for {:?} but got \
+                                                                 ack for {:?}",
+                                                                pb,
+                                                                ack_pb)))
                                 } else {
                                     Ok(())
                                 }
@@ -492,46 +507,55 @@ impl ReadDirectoryChangesWatcher {
 }
 
 impl Watcher for ReadDirectoryChangesWatcher {
-    fn new_raw(tx: Sender<RawEvent>) -> Result<ReadDirectoryChangesWatcher> {
+    fn new_raw<F: EventHandler<RawEvent>>(handler: F) -> Result<ReadDirectoryChangesWatcher> {
         // create dummy channel for meta event
         let (meta_tx, _) = channel();
-        ReadDirectoryChangesWatcher::create(tx, meta_tx)
+        ReadDirectoryChangesWatcher::create(handler, meta_tx)
     }
 
-    fn new(tx: Sender<DebouncedEvent>, delay: Duration) -> Result<ReadDirectoryChangesWatcher> {
+    fn new<F: EventHandler<DebouncedEvent>>(handler: F,
+                                            delay: Duration)
+                                            -> Result<ReadDirectoryChangesWatcher> {
         // create dummy channel for meta event
         let (meta_tx, _) = channel();
-        ReadDirectoryChangesWatcher::create_debounced(tx, meta_tx, delay)
+        ReadDirectoryChangesWatcher::create_debounced(handler, meta_tx, delay)
     }
 
-    fn watch<P: AsRef<Path>>(&mut self, path: P, recursive_mode: RecursiveMode) -> Result<()> {
-        let pb = if path.as_ref().is_absolute() {
-            path.as_ref().to_owned()
+    fn watch(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()> {
+        let pb = if path.is_absolute() {
+            path.to_owned()
         } else {
-            let p = try!(env::current_dir().map_err(Error::Io));
+            let p = try!(env::current_dir().map_err(Error::io));
             p.join(path)
         };
         // path must exist and be either a file or directory
         if !pb.is_dir() && !pb.is_file() {
-            return Err(Error::Generic("Input watch path is neither a file nor a directory."
-                .to_owned()));
+            return Err(Error::generic("Input watch path is neither a file nor a directory.")
+                .add_path(pb.clone()));
         }
         self.send_action_require_ack(Action::Watch(pb.clone(), recursive_mode), &pb)
     }
 
-    fn unwatch<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        let pb = if path.as_ref().is_absolute() {
-            path.as_ref().to_owned()
+    fn unwatch(&mut self, path: &Path) -> Result<()> {
+        let pb = if path.is_absolute() {
+            path.to_owned()
         } else {
-            let p = try!(env::current_dir().map_err(Error::Io));
+            let p = try!(env::current_dir().map_err(Error::io));
             p.join(path)
         };
         let res = self.tx
             .send(Action::Unwatch(pb))
-            .map_err(|_| Error::Generic("Error sending to internal channel".to_owned()));
+            .map_err(|_| Error::generic("Error sending to internal channel"));
         self.wakeup_server();
         res
     }
+
+    fn configure(&mut self, option: Config) -> Result<bool> {
+        match option {
+            Config::DebounceDelay(delay) => Ok(self.event_tx.lock().unwrap().set_delay(delay)),
+            Config::PollInterval(_) | Config::RenameWindow(_) => Ok(false),
+        }
+    }
 }
 
 impl Drop for ReadDirectoryChangesWatcher {
@@ -32,6 +32,13 @@ fn recv_events_debounced(rx: &mpsc::Receiver<DebouncedEvent>) -> Vec<DebouncedEv
     events
 }
 
+/// Create a debounced watcher, a `PollWatcher` scans every 50 ms
+fn debounced_watcher<W: Watcher>(tx: mpsc::Sender<DebouncedEvent>) -> W {
+    let mut watcher: W = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
+    watcher.configure(Config::PollInterval(Duration::from_millis(50))).expect("failed to configure debounced watcher");
+    watcher
+}
+
 #[test]
 fn create_file() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
@@ -40,7 +47,45 @@ fn create_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.create("file1");
+
+    assert_eq!(recv_events_debounced(&rx), vec![
+        DebouncedEvent::Create(tdir.mkpath("file1")),
+    ]);
+}
+
+#[test]
+fn create_file_closure_handler() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new(move |event: DebouncedEvent| {
+        tx.send(event).unwrap();
+    }, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.create("file1");
+
+    assert_eq!(recv_events_debounced(&rx), vec![
+        DebouncedEvent::Create(tdir.mkpath("file1")),
+    ]);
+}
+
+#[test]
+fn configure_debounce_delay() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_secs(60)).expect("failed to create debounced watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    assert!(watcher.configure(Config::DebounceDelay(Duration::from_millis(DELAY_MS))).expect("failed to configure debounced watcher"));
 
     tdir.create("file1");
 
@@ -61,7 +106,7 @@ fn write_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.write("file1");
 
@@ -83,7 +128,7 @@ fn write_long_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     let wait = Duration::from_millis(DELAY_MS / 2);
     tdir.write("file1");
@@ -127,7 +172,7 @@ fn modify_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.chmod("file1");
 
@@ -144,6 +189,47 @@ fn modify_file() {
     }
 }
 
+#[test]
+fn poll_modify_file() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: PollWatcher = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.chmod("file1");
+
+    assert_eq!(recv_events_debounced(&rx), vec![
+        DebouncedEvent::Chmod(tdir.mkpath("file1")),
+    ]);
+}
+
+#[test]
+fn poll_create_file_in_dir() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: PollWatcher = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second for directories
+
+    tdir.create("dir1/file1");
+
+    // the write of dir1 isn't reported, like with inotify
+    assert_eq!(recv_events_debounced(&rx), vec![
+        DebouncedEvent::Create(tdir.mkpath("dir1/file1")),
+    ]);
+}
+
 #[test]
 fn delete_file() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
@@ -156,7 +242,7 @@ fn delete_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.remove("file1");
 
@@ -168,6 +254,16 @@ fn delete_file() {
 
 #[test]
 fn rename_file() {
+    rename_file_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)] // the PollWatcher only matches renamed files by inode on Unix
+fn poll_rename_file() {
+    rename_file_with::<PollWatcher>();
+}
+
+fn rename_file_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -177,8 +273,8 @@ fn rename_file() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.rename("file1", "file2");
 
@@ -196,7 +292,7 @@ fn create_write_modify_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("file1");
     tdir.write("file1");
@@ -215,7 +311,7 @@ fn create_delete_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("file1");
     sleep_macos(10);
@@ -236,7 +332,7 @@ fn delete_create_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.remove("file1");
     sleep_macos(10);
@@ -250,13 +346,23 @@ fn delete_create_file() {
 
 #[test]
 fn create_rename_file() {
+    create_rename_file_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_create_rename_file() {
+    create_rename_file_with::<PollWatcher>();
+}
+
+fn create_rename_file_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     sleep_macos(10);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("file1");
     tdir.rename("file1", "file2");
@@ -268,13 +374,23 @@ fn create_rename_file() {
 
 #[test]
 fn create_rename_delete_file() {
+    create_rename_delete_file_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_create_rename_delete_file() {
+    create_rename_delete_file_with::<PollWatcher>();
+}
+
+fn create_rename_delete_file_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     sleep_macos(10);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("file1");
     sleep_macos(10);
@@ -297,6 +413,7 @@ fn create_rename_delete_file() {
 // right: `[]`)',
 // tests/debounce.rs:273
 
+// not run against the PollWatcher, which sees the overwritten file replaced by another one, not a rename
 #[test]
 fn create_rename_overwrite_file() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
@@ -309,7 +426,7 @@ fn create_rename_overwrite_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("file1");
     tdir.rename("file1", "file2");
@@ -335,13 +452,23 @@ fn create_rename_overwrite_file() {
 // https://github.com/passcod/notify/issues/99
 #[test]
 fn create_rename_write_create() { // fsevents
+    create_rename_write_create_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_create_rename_write_create() {
+    create_rename_write_create_with::<PollWatcher>();
+}
+
+fn create_rename_write_create_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     sleep_macos(10);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("file1");
     tdir.rename("file1", "file2");
@@ -358,13 +485,23 @@ fn create_rename_write_create() { // fsevents
 // https://github.com/passcod/notify/issues/100
 #[test]
 fn create_rename_remove_create() { // fsevents
+    create_rename_remove_create_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_create_rename_remove_create() {
+    create_rename_remove_create_with::<PollWatcher>();
+}
+
+fn create_rename_remove_create_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     sleep_macos(10);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("file1");
 
@@ -394,6 +531,16 @@ fn create_rename_remove_create() { // fsevents
 // https://github.com/passcod/notify/issues/101
 #[test]
 fn move_out_sleep_move_in() { // fsevents
+    move_out_sleep_move_in_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_move_out_sleep_move_in() {
+    move_out_sleep_move_in_with::<PollWatcher>();
+}
+
+fn move_out_sleep_move_in_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create("watch_dir");
@@ -401,8 +548,8 @@ fn move_out_sleep_move_in() { // fsevents
     sleep_macos(10);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("watch_dir/file1");
     tdir.rename("watch_dir/file1", "file1");
@@ -436,7 +583,7 @@ fn move_repeatedly() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("watch_dir/file1");
 
@@ -461,6 +608,16 @@ fn move_repeatedly() {
 
 #[test]
 fn write_rename_file() {
+    write_rename_file_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_write_rename_file() {
+    write_rename_file_with::<PollWatcher>();
+}
+
+fn write_rename_file_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -470,8 +627,8 @@ fn write_rename_file() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.write("file1");
     tdir.rename("file1", "file2");
@@ -484,6 +641,7 @@ fn write_rename_file() {
     ]);
 }
 
+// not run against the PollWatcher, which can't tell that the file was written after the rename
 #[test]
 fn rename_write_file() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
@@ -496,7 +654,7 @@ fn rename_write_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.rename("file1", "file2");
     sleep_macos(10);
@@ -512,6 +670,16 @@ fn rename_write_file() {
 
 #[test]
 fn modify_rename_file() {
+    modify_rename_file_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_modify_rename_file() {
+    modify_rename_file_with::<PollWatcher>();
+}
+
+fn modify_rename_file_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -521,8 +689,8 @@ fn modify_rename_file() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.chmod("file1");
     tdir.rename("file1", "file2");
@@ -546,6 +714,16 @@ fn modify_rename_file() {
 
 #[test]
 fn rename_modify_file() {
+    rename_modify_file_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_rename_modify_file() {
+    rename_modify_file_with::<PollWatcher>();
+}
+
+fn rename_modify_file_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -555,8 +733,8 @@ fn rename_modify_file() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.rename("file1", "file2");
     sleep_macos(10);
@@ -581,6 +759,16 @@ fn rename_modify_file() {
 
 #[test]
 fn rename_rename_file() {
+    rename_rename_file_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_rename_rename_file() {
+    rename_rename_file_with::<PollWatcher>();
+}
+
+fn rename_rename_file_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -590,8 +778,8 @@ fn rename_rename_file() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.rename("file1", "file2");
     sleep_macos(10);
@@ -615,7 +803,7 @@ fn write_delete_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.write("file1");
     tdir.remove("file1");
@@ -635,7 +823,7 @@ fn create_directory() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("dir1");
 
@@ -653,7 +841,7 @@ fn create_directory_watch_subdirectories() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("dir1");
     tdir.create("dir1/dir2");
@@ -662,18 +850,11 @@ fn create_directory_watch_subdirectories() {
 
     tdir.create("dir1/dir2/file1");
 
-    if cfg!(target_os="linux") {
-        assert_eq!(recv_events_debounced(&rx), vec![
-            DebouncedEvent::Create(tdir.mkpath("dir1")),
-            DebouncedEvent::Create(tdir.mkpath("dir1/dir2/file1")),
-        ]);
-    } else {/*  */
-        assert_eq!(recv_events_debounced(&rx), vec![
-            DebouncedEvent::Create(tdir.mkpath("dir1")),
-            DebouncedEvent::Create(tdir.mkpath("dir1/dir2")),
-            DebouncedEvent::Create(tdir.mkpath("dir1/dir2/file1")),
-        ]);
-    }
+    assert_eq!(recv_events_debounced(&rx), vec![
+        DebouncedEvent::Create(tdir.mkpath("dir1")),
+        DebouncedEvent::Create(tdir.mkpath("dir1/dir2")),
+        DebouncedEvent::Create(tdir.mkpath("dir1/dir2/file1")),
+    ]);
 }
 
 #[test]
@@ -688,7 +869,7 @@ fn modify_directory() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.chmod("dir1");
 
@@ -717,7 +898,7 @@ fn delete_directory() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.remove("dir1");
 
@@ -729,6 +910,16 @@ fn delete_directory() {
 
 #[test]
 fn rename_directory() {
+    rename_directory_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_rename_directory() {
+    rename_directory_with::<PollWatcher>();
+}
+
+fn rename_directory_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -738,8 +929,8 @@ fn rename_directory() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.rename("dir1", "dir2");
 
@@ -757,7 +948,7 @@ fn create_modify_directory() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("dir1");
     tdir.chmod("dir1");
@@ -775,7 +966,7 @@ fn create_delete_directory() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("dir1");
     sleep_macos(10);
@@ -796,7 +987,7 @@ fn delete_create_directory() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.remove("dir1");
     sleep_macos(10);
@@ -810,13 +1001,23 @@ fn delete_create_directory() {
 
 #[test]
 fn create_rename_directory() {
+    create_rename_directory_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_create_rename_directory() {
+    create_rename_directory_with::<PollWatcher>();
+}
+
+fn create_rename_directory_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     sleep_macos(10);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("dir1");
     tdir.rename("dir1", "dir2");
@@ -828,13 +1029,23 @@ fn create_rename_directory() {
 
 #[test]
 fn create_rename_delete_directory() {
+    create_rename_delete_directory_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_create_rename_delete_directory() {
+    create_rename_delete_directory_with::<PollWatcher>();
+}
+
+fn create_rename_delete_directory_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     sleep_macos(10);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("dir1");
     sleep_macos(10);
@@ -845,6 +1056,7 @@ fn create_rename_delete_directory() {
     assert_eq!(recv_events_debounced(&rx), vec![]);
 }
 
+// not run against the PollWatcher, which sees the overwritten directory replaced by another one, not a rename
 #[test]
 #[cfg(not(target_os="windows"))]
 fn create_rename_overwrite_directory() {
@@ -863,7 +1075,7 @@ fn create_rename_overwrite_directory() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.create("dir1");
     tdir.rename("dir1", "dir2");
@@ -882,6 +1094,16 @@ fn create_rename_overwrite_directory() {
 
 #[test]
 fn modify_rename_directory() {
+    modify_rename_directory_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_modify_rename_directory() {
+    modify_rename_directory_with::<PollWatcher>();
+}
+
+fn modify_rename_directory_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -891,8 +1113,8 @@ fn modify_rename_directory() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.chmod("dir1");
     tdir.chmod("dir1"); // needed by os x
@@ -917,6 +1139,16 @@ fn modify_rename_directory() {
 
 #[test]
 fn rename_modify_directory() {
+    rename_modify_directory_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_rename_modify_directory() {
+    rename_modify_directory_with::<PollWatcher>();
+}
+
+fn rename_modify_directory_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -926,8 +1158,8 @@ fn rename_modify_directory() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.rename("dir1", "dir2");
     sleep_macos(10);
@@ -965,6 +1197,16 @@ fn rename_modify_directory() {
 
 #[test]
 fn rename_rename_directory() {
+    rename_rename_directory_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_rename_rename_directory() {
+    rename_rename_directory_with::<PollWatcher>();
+}
+
+fn rename_rename_directory_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -974,8 +1216,8 @@ fn rename_rename_directory() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.rename("dir1", "dir2");
     sleep_macos(10);
@@ -999,7 +1241,7 @@ fn modify_delete_directory() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.chmod("dir1");
     tdir.chmod("dir1"); // needed by windows
@@ -1023,6 +1265,16 @@ fn modify_delete_directory() {
 // https://github.com/passcod/notify/issues/124
 #[test]
 fn move_in_directory_watch_subdirectories() {
+    move_in_directory_watch_subdirectories_with::<RecommendedWatcher>();
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_move_in_directory_watch_subdirectories() {
+    move_in_directory_watch_subdirectories_with::<PollWatcher>();
+}
+
+fn move_in_directory_watch_subdirectories_with<W: Watcher>() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
@@ -1033,8 +1285,8 @@ fn move_in_directory_watch_subdirectories() {
     sleep_macos(35_000);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+    let mut watcher: W = debounced_watcher(tx);
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
 
     tdir.rename("dir1", "watch_dir/dir1");
 
@@ -1042,13 +1294,22 @@ fn move_in_directory_watch_subdirectories() {
 
     tdir.create("watch_dir/dir1/dir2/file1");
 
-    assert_eq!(recv_events_debounced(&rx), vec![
-        DebouncedEvent::Create(tdir.mkpath("watch_dir/dir1")),
-        DebouncedEvent::Create(tdir.mkpath("watch_dir/dir1/dir2/file1")),
-    ]);
+    if cfg!(target_os="linux") {
+        assert_eq!(recv_events_debounced(&rx), vec![
+            DebouncedEvent::Create(tdir.mkpath("watch_dir/dir1")),
+            DebouncedEvent::Create(tdir.mkpath("watch_dir/dir1/dir2")),
+            DebouncedEvent::Create(tdir.mkpath("watch_dir/dir1/dir2/file1")),
+        ]);
+    } else {
+        assert_eq!(recv_events_debounced(&rx), vec![
+            DebouncedEvent::Create(tdir.mkpath("watch_dir/dir1")),
+            DebouncedEvent::Create(tdir.mkpath("watch_dir/dir1/dir2/file1")),
+        ]);
+    }
 }
 
 // https://github.com/passcod/notify/issues/129
+// not run against the PollWatcher, which sees the file replaced by another one, not a rename
 #[test]
 fn rename_create_remove_temp_file() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
@@ -1058,7 +1319,7 @@ fn rename_create_remove_temp_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep_macos(10);
     tdir.rename("file1", "file2");
@@ -1072,6 +1333,7 @@ fn rename_create_remove_temp_file() {
      ]);
 }
 
+// not run against the PollWatcher, which sees the file replaced by another one, not a rename
 #[test]
 fn rename_rename_remove_temp_file() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
@@ -1082,7 +1344,7 @@ fn rename_rename_remove_temp_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep_macos(10);
     tdir.rename("file1", "file2");
@@ -1107,3 +1369,25 @@ fn rename_rename_remove_temp_file() {
         ]);
     }
 }
+
+#[test]
+#[cfg(target_os="linux")]
+fn delete_watched_file() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(DELAY_MS)).expect("failed to create debounced watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::NonRecursive).expect("failed to watch file");
+
+    tdir.remove("file1");
+
+    assert_eq!(recv_events_debounced(&rx), vec![
+        DebouncedEvent::NoticeRemove(tdir.mkpath("file1")),
+        DebouncedEvent::WatchDropped(tdir.mkpath("file1")),
+        DebouncedEvent::Remove(tdir.mkpath("file1")),
+    ]);
+}
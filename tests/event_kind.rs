@@ -0,0 +1,167 @@
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use notify::event::*;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempdir::TempDir;
+
+use utils::*;
+
+fn recv_kinds(rx: &Receiver<RawEvent>) -> Vec<(PathBuf, EventKind, Option<u32>)> {
+    let start = Instant::now();
+
+    let mut evs = Vec::new();
+
+    while start.elapsed() < Duration::from_millis(100) {
+        match rx.try_recv() {
+            Ok(RawEvent { path: Some(path), op: Ok(op), kind: Some(kind), cookie, .. }) => {
+                assert_eq!(op, Op::from(kind));
+                evs.push((path, kind, cookie));
+            }
+            Ok(RawEvent { op: Err(e), .. }) => panic!("unexpected event err: {:?}", e),
+            Ok(event) => panic!("event without kind: {:?}", event),
+            Err(TryRecvError::Empty) => (),
+            Err(e) => panic!("unexpected channel err: {:?}", e),
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    evs
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_create_kinds() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.create("file1");
+    tdir.create("dir1");
+
+    assert_eq!(recv_kinds(&rx), vec![
+        (tdir.mkpath("file1"), EventKind::Create(CreateKind::File), None),
+        (tdir.mkpath("file1"), EventKind::Access(AccessKind::Close(AccessMode::Write)), None),
+        (tdir.mkpath("dir1"), EventKind::Create(CreateKind::Folder), None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_modify_kinds() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.write("file1");
+    tdir.chmod("file1");
+
+    assert_eq!(recv_kinds(&rx), vec![
+        (tdir.mkpath("file1"), EventKind::Modify(ModifyKind::Data(DataChange::Content)), None),
+        (tdir.mkpath("file1"), EventKind::Access(AccessKind::Close(AccessMode::Write)), None),
+        (tdir.mkpath("file1"), EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any)), None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_rename_kinds() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1a",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.rename("file1a", "file1b");
+
+    let actual = recv_kinds(&rx);
+    let cookie = actual[0].2;
+    assert!(cookie.is_some());
+    assert_eq!(actual, vec![
+        (tdir.mkpath("file1a"), EventKind::Modify(ModifyKind::Name(RenameMode::From)), cookie),
+        (tdir.mkpath("file1b"), EventKind::Modify(ModifyKind::Name(RenameMode::To)), cookie),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_remove_kinds() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+        "dir1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::NonRecursive).expect("failed to watch directory");
+
+    tdir.remove("file1");
+    tdir.remove("dir1");
+
+    assert_eq!(recv_kinds(&rx), vec![
+        (tdir.mkpath("file1"), EventKind::Remove(RemoveKind::File), None),
+        (tdir.mkpath("dir1"), EventKind::Remove(RemoveKind::Folder), None),
+    ]);
+}
+
+#[test]
+fn poll_create_and_remove_kinds() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+
+    tdir.create("dir1/dir2");
+    tdir.create("dir1/file1");
+
+    sleep(100);
+
+    let mut actual = recv_kinds(&rx);
+    actual.sort_by(|a, b| a.0.cmp(&b.0));
+
+    assert_eq!(actual, vec![
+        (tdir.mkpath("dir1"), EventKind::Modify(ModifyKind::Data(DataChange::Any)), None),
+        (tdir.mkpath("dir1/dir2"), EventKind::Create(CreateKind::Folder), None),
+        (tdir.mkpath("dir1/file1"), EventKind::Create(CreateKind::File), None),
+    ]);
+
+    tdir.remove("dir1/dir2");
+    tdir.remove("dir1/file1");
+
+    sleep(100);
+
+    let mut actual = recv_kinds(&rx);
+    actual.sort_by(|a, b| a.0.cmp(&b.0));
+    actual.retain(|&(ref path, _, _)| path != &tdir.mkpath("dir1"));
+
+    assert_eq!(actual, vec![
+        (tdir.mkpath("dir1/dir2"), EventKind::Remove(RemoveKind::Folder), None),
+        (tdir.mkpath("dir1/file1"), EventKind::Remove(RemoveKind::File), None),
+    ]);
+}
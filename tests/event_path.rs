@@ -52,7 +52,7 @@ fn watch_relative() {
 
         let (tx, rx) = mpsc::channel();
         let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-        watcher.watch("dir1", RecursiveMode::Recursive).expect("failed to watch directory");
+        watcher.watch_path("dir1", RecursiveMode::Recursive).expect("failed to watch directory");
 
         sleep_windows(100);
 
@@ -76,7 +76,7 @@ fn watch_relative() {
 
         let (tx, rx) = mpsc::channel();
         let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-        watcher.watch("file1", RecursiveMode::Recursive).expect("failed to watch file");
+        watcher.watch_path("file1", RecursiveMode::Recursive).expect("failed to watch file");
 
         sleep_windows(100);
 
@@ -101,7 +101,7 @@ fn watch_relative() {
 
         let (tx, rx) = mpsc::channel();
         let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-        watcher.watch("dir1", RecursiveMode::Recursive).expect("failed to watch directory");
+        watcher.watch_path("dir1", RecursiveMode::Recursive).expect("failed to watch directory");
 
         sleep_windows(100);
 
@@ -120,7 +120,7 @@ fn watch_relative() {
 
         let (tx, rx) = mpsc::channel();
         let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-        watcher.watch("file1", RecursiveMode::Recursive).expect("failed to watch file");
+        watcher.watch_path("file1", RecursiveMode::Recursive).expect("failed to watch file");
 
         sleep_windows(100);
 
@@ -319,3 +319,67 @@ fn watch_canonicalized_network_file() {
         (watch_path, op::WRITE, None),
     ]);
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn inotify_rename_watched_subdirectory() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+    tdir.create_all(vec![
+        "dir1a/dir2",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.rename("dir1a", "dir1b");
+    tdir.create("dir1b/file1");
+    tdir.create("dir1b/dir2/file2");
+
+    let actual = recv_events_simple(&rx);
+    let cookies = extract_cookies(&actual);
+    assert_eq!(cookies.len(), 1);
+    assert_eq!(actual, vec![
+        (tdir.mkpath("dir1a"), op::RENAME, Some(cookies[0])),
+        (tdir.mkpath("dir1b"), op::RENAME, Some(cookies[0])),
+        (tdir.mkpath("dir1b/file1"), op::CREATE, None),
+        (tdir.mkpath("dir1b/dir2/file2"), op::CREATE, None),
+    ]);
+
+    tdir.rename("dir1b", "dir1c");
+    sleep(10);
+    tdir.write("dir1c/dir2/file2");
+
+    let mut actual = recv_events_simple(&rx);
+    actual.retain(|&(_, op, _)| op != op::RENAME);
+    assert_eq!(actual, vec![
+        (tdir.mkpath("dir1c/dir2/file2"), op::WRITE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn inotify_rename_parent_of_watched_directory() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+    tdir.create_all(vec![
+        "dir1a/dir2",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("dir1a/dir2"), RecursiveMode::NonRecursive).expect("failed to watch directory");
+
+    tdir.rename("dir1a", "dir1b");
+    sleep(10);
+    tdir.create("dir1b/dir2/file1");
+
+    let mut actual = recv_events_simple(&rx);
+    actual.retain(|&(_, op, _)| op != op::RENAME);
+    assert_eq!(actual, vec![
+        (tdir.mkpath("dir1b/dir2/file1"), op::CREATE, None),
+    ]);
+
+    // the watched path follows the rename
+    watcher.unwatch(&tdir.mkpath("dir1b/dir2")).expect("failed to unwatch directory");
+}
@@ -0,0 +1,154 @@
+#![cfg(target_os="linux")]
+
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::process;
+use std::sync::mpsc::{self, Receiver};
+use tempdir::TempDir;
+
+use utils::*;
+
+/// Create a fanotify watcher, `None` if the process isn't allowed to use fanotify
+fn fanotify_watcher() -> Option<(FanotifyWatcher, Receiver<RawEvent>)> {
+    let (tx, rx) = mpsc::channel();
+    match FanotifyWatcher::new_raw(tx) {
+        Ok(watcher) => Some((watcher, rx)),
+        Err(Error { kind: ErrorKind::PermissionDenied(_), .. }) => None,
+        Err(e) => panic!("failed to create fanotify watcher: {}", e),
+    }
+}
+
+#[test]
+fn fanotify_create_write() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/dir1",
+        "outside_dir",
+    ]);
+
+    let (mut watcher, rx) = match fanotify_watcher() {
+        Some(w) => w,
+        None => return,
+    };
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.create("outside_dir/file1");
+    tdir.create("watch_dir/dir1/file1");
+    tdir.create("watch_dir/dir2");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("watch_dir/dir1/file1"), op::CREATE, None),
+        (tdir.mkpath("watch_dir/dir1/file1"), op::CLOSE_WRITE, None),
+        (tdir.mkpath("watch_dir/dir2"), op::CREATE, None),
+    ]);
+
+    tdir.write("watch_dir/dir1/file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("watch_dir/dir1/file1"), op::WRITE, None),
+        (tdir.mkpath("watch_dir/dir1/file1"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+fn fanotify_non_recursive() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/dir1",
+    ]);
+
+    let (mut watcher, rx) = match fanotify_watcher() {
+        Some(w) => w,
+        None => return,
+    };
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::NonRecursive).expect("failed to watch directory");
+
+    tdir.create("watch_dir/dir1/file1");
+    tdir.remove("watch_dir/dir1/file1");
+    tdir.remove("watch_dir/dir1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("watch_dir/dir1"), op::REMOVE, None),
+    ]);
+}
+
+#[test]
+fn fanotify_rename() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/file1",
+        "watch_dir/file2",
+        "outside_dir/file3",
+    ]);
+
+    let (mut watcher, rx) = match fanotify_watcher() {
+        Some(w) => w,
+        None => return,
+    };
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.rename("watch_dir/file1", "watch_dir/file1_renamed");
+    tdir.rename("watch_dir/file2", "outside_dir/file2");
+    tdir.rename("outside_dir/file3", "watch_dir/file3");
+
+    let actual = recv_events(&rx);
+    let cookies = extract_cookies(&actual);
+    assert_eq!(cookies.len(), 1);
+    assert_eq!(actual, vec![
+        (tdir.mkpath("watch_dir/file1"), op::RENAME, Some(cookies[0])),
+        (tdir.mkpath("watch_dir/file1_renamed"), op::RENAME, Some(cookies[0])),
+        (tdir.mkpath("watch_dir/file2"), op::REMOVE, None),
+        (tdir.mkpath("watch_dir/file3"), op::CREATE, None),
+    ]);
+}
+
+#[test]
+fn fanotify_pid() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir",
+    ]);
+
+    let (mut watcher, rx) = match fanotify_watcher() {
+        Some(w) => w,
+        None => return,
+    };
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.create("watch_dir/file1");
+
+    let event = rx.recv().expect("failed to receive event");
+    assert_eq!(event.path, Some(tdir.mkpath("watch_dir/file1")));
+    assert_eq!(event.pid, Some(process::id()));
+}
+
+#[test]
+fn fanotify_unwatch() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir",
+    ]);
+
+    let (mut watcher, rx) = match fanotify_watcher() {
+        Some(w) => w,
+        None => return,
+    };
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+    // a single mark for the whole filesystem
+    assert_eq!(watcher.stats().expect("failed to get stats").descriptors, 1);
+    watcher.unwatch(&tdir.mkpath("watch_dir")).expect("failed to unwatch directory");
+    assert!(watcher.unwatch(&tdir.mkpath("watch_dir")).is_err());
+
+    tdir.create("watch_dir/file1");
+
+    assert_eq!(recv_events(&rx), vec![]);
+}
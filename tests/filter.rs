@@ -0,0 +1,181 @@
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::fs;
+use std::sync::mpsc;
+use tempdir::TempDir;
+
+use utils::*;
+
+fn create_dirs(tdir: &TempDir, paths: Vec<&str>) {
+    for p in paths {
+        fs::create_dir_all(tdir.mkpath(p)).expect("failed to create directory");
+    }
+}
+
+fn exclude_filter() -> PathFilter {
+    PathFilter::new()
+        .exclude("target").expect("invalid pattern")
+        .exclude("**/node_modules").expect("invalid pattern")
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_exclude_existing_directories() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    create_dirs(&tdir, vec![
+        "target/debug",
+        "web/node_modules/dir1",
+        "src/dir1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("."), WatchOptions {
+        recursive_mode: RecursiveMode::Recursive,
+        filter: exclude_filter(),
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    tdir.create("target/debug/file1");
+    tdir.create("target/file1");
+    tdir.create("web/node_modules/dir1/file1");
+    tdir.create("src/dir1/file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("src/dir1/file1"), op::CREATE, None),
+        (tdir.mkpath("src/dir1/file1"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_exclude_created_directories() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("."), WatchOptions {
+        recursive_mode: RecursiveMode::Recursive,
+        filter: exclude_filter(),
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    create_dirs(&tdir, vec![
+        "web/node_modules",
+    ]);
+
+    sleep(10);
+
+    tdir.create("web/node_modules/file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("web"), op::CREATE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_exclude_moved_in_contents() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    create_dirs(&tdir, vec![
+        "watched",
+        "web/node_modules/dir1",
+        "web/src",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("watched"), WatchOptions {
+        recursive_mode: RecursiveMode::Recursive,
+        filter: exclude_filter(),
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    tdir.rename("web", "watched/web");
+
+    let mut actual = recv_events(&rx);
+    actual.sort_by(|a, b| a.0.cmp(&b.0));
+
+    assert_eq!(actual, vec![
+        (tdir.mkpath("watched/web"), op::CREATE, None),
+        (tdir.mkpath("watched/web/src"), op::CREATE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_include_files() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    create_dirs(&tdir, vec![
+        "src",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("."), WatchOptions {
+        recursive_mode: RecursiveMode::Recursive,
+        filter: PathFilter::new().include("**/*.rs").expect("invalid pattern"),
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    tdir.create("src/lib.c");
+    tdir.create("src/lib.rs");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("src/lib.rs"), op::CREATE, None),
+        (tdir.mkpath("src/lib.rs"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+fn poll_exclude_directories() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    create_dirs(&tdir, vec![
+        "target/debug",
+        "src",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch_with(&tdir.mkpath("."), WatchOptions {
+        recursive_mode: RecursiveMode::Recursive,
+        filter: exclude_filter(),
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+
+    tdir.create("target/debug/file1");
+    tdir.create("web/node_modules/file1");
+    tdir.create("src/file1");
+
+    sleep(100);
+
+    let mut actual = recv_events(&rx);
+    actual.sort_by(|a, b| a.0.cmp(&b.0));
+
+    assert_eq!(actual, vec![
+        (tdir.mkpath("."), op::WRITE, None), // parent directory gets modified
+        (tdir.mkpath("src"), op::WRITE, None),
+        (tdir.mkpath("src/file1"), op::CREATE, None),
+        (tdir.mkpath("web"), op::CREATE, None),
+    ]);
+}
+
+#[test]
+fn null_accepts_filters() {
+    let mut watcher = NullWatcher;
+    watcher.watch_with(std::path::Path::new("/"), WatchOptions {
+        recursive_mode: RecursiveMode::Recursive,
+        filter: exclude_filter(),
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+}
@@ -0,0 +1,138 @@
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::sync::mpsc;
+use tempdir::TempDir;
+
+use utils::*;
+
+fn follow() -> WatchOptions {
+    WatchOptions {
+        follow_path: true,
+        ..WatchOptions::default()
+    }
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_follow_path_atomic_replace() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "config",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("config"), follow()).expect("failed to watch file");
+
+    tdir.create("config.tmp");
+    tdir.write("config.tmp");
+    tdir.rename("config.tmp", "config");
+    tdir.write("config");
+    tdir.create("other");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("config"), op::WRITE, None),
+        (tdir.mkpath("config"), op::WRITE, None),
+        (tdir.mkpath("config"), op::CLOSE_WRITE, None),
+    ]);
+
+    watcher.unwatch(&tdir.mkpath("config")).expect("failed to unwatch file");
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_follow_path_create_remove() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("config"), follow()).expect("failed to watch file");
+
+    tdir.create("config");
+    tdir.rename("config", "config.old");
+    tdir.create("config.new");
+    tdir.rename("config.new", "config");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("config"), op::CREATE, None),
+        (tdir.mkpath("config"), op::CLOSE_WRITE, None),
+        (tdir.mkpath("config"), op::REMOVE, None),
+        (tdir.mkpath("config"), op::CREATE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_follow_path_with_watched_parent() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "config",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::NonRecursive).expect("failed to watch directory");
+    watcher.watch_with(&tdir.mkpath("config"), follow()).expect("failed to watch file");
+    watcher.unwatch(&tdir.mkpath("config")).expect("failed to unwatch file");
+
+    tdir.create("file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::CREATE, None),
+        (tdir.mkpath("file1"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+fn poll_follow_path_atomic_replace() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "config",
+        "config.tmp",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch_with(&tdir.mkpath("config"), follow()).expect("failed to watch file");
+
+    // the replacement has the same modification time in seconds
+    tdir.rename("config.tmp", "config");
+
+    sleep(100);
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("config"), op::WRITE, None),
+    ]);
+}
+
+#[test]
+fn poll_follow_path_create_remove() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch_with(&tdir.mkpath("config"), follow()).expect("failed to watch file");
+
+    sleep(100);
+
+    tdir.create("config");
+
+    sleep(100);
+
+    tdir.remove("config");
+
+    sleep(100);
+
+    // a missing path isn't an error
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("config"), op::CREATE, None),
+        (tdir.mkpath("config"), op::REMOVE, None),
+    ]);
+}
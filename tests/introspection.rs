@@ -0,0 +1,131 @@
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use tempdir::TempDir;
+
+use utils::*;
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_watched_paths() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/dir2",
+        "file1",
+    ]);
+
+    let (tx, _rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    assert_eq!(watcher.watched_paths().expect("failed to list watched paths"), Vec::<PathBuf>::new());
+
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::NonRecursive).expect("failed to watch file");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    // directories watched as part of a recursive watch aren't listed
+    assert_eq!(watcher.watched_paths().expect("failed to list watched paths"), vec![
+        tdir.mkpath("dir1"),
+        tdir.mkpath("file1"),
+    ]);
+
+    watcher.unwatch(&tdir.mkpath("file1")).expect("failed to unwatch file");
+
+    assert_eq!(watcher.watched_paths().expect("failed to list watched paths"), vec![
+        tdir.mkpath("dir1"),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_stats() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/dir2/dir3",
+        "dir1/file1",
+    ]);
+
+    let (tx, _rx) = mpsc::channel();
+    let mut watcher: UnthreadedINotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("dir1/dir2"), RecursiveMode::NonRecursive).expect("failed to watch directory");
+
+    let stats = watcher.stats().expect("failed to get stats");
+    assert_eq!(stats.roots, 2);
+    // one watch per directory, shared by the overlapping watched paths
+    assert_eq!(stats.descriptors, 3);
+    assert_eq!(stats.polled_files, 0);
+    assert!(stats.max_user_watches.expect("failed to read the watch limit") > 0);
+
+    tdir.create("dir1/dir4");
+    watcher.process_events();
+
+    assert_eq!(watcher.stats().expect("failed to get stats").descriptors, 4);
+}
+
+#[test]
+fn poll_watched_paths_and_stats() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/dir2/file1",
+        "file2",
+    ]);
+
+    let (tx, _rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("file2"), RecursiveMode::NonRecursive).expect("failed to watch file");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    assert_eq!(watcher.watched_paths().expect("failed to list watched paths"), vec![
+        tdir.mkpath("dir1"),
+        tdir.mkpath("file2"),
+    ]);
+    assert_eq!(watcher.stats().expect("failed to get stats"), WatcherStats {
+        roots: 2,
+        descriptors: 0,
+        polled_files: 4,
+        max_user_watches: None,
+    });
+
+    tdir.create("dir1/file3");
+    sleep(100);
+
+    assert_eq!(watcher.stats().expect("failed to get stats").polled_files, 5);
+}
+
+#[test]
+fn poll_watched_paths_relative() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1",
+    ]);
+
+    // the only test of this file changing the working directory
+    env::set_current_dir(tdir.path()).expect("failed to change working directory");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch(Path::new("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    assert_eq!(watcher.watched_paths().expect("failed to list watched paths"), vec![
+        tdir.mkpath("dir1"),
+    ]);
+
+    tdir.create("dir1/file1");
+
+    // events carry the absolute path as well, dir1 may be reported as written too
+    let events = recv_events(&rx);
+    assert!(events.contains(&(tdir.mkpath("dir1/file1"), op::CREATE, None)), "{:?}", events);
+
+    watcher.unwatch(Path::new("dir1")).expect("failed to unwatch directory");
+
+    assert_eq!(watcher.watched_paths().expect("failed to list watched paths"), Vec::<PathBuf>::new());
+}
@@ -0,0 +1,122 @@
+#![cfg(target_os="linux")]
+
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::sync::mpsc;
+use tempdir::TempDir;
+
+use utils::*;
+
+#[test]
+fn inotify_unwatch_outer_directory() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/dir2/dir3",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("dir1/dir2"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.unwatch(&tdir.mkpath("dir1")).expect("failed to unwatch directory");
+
+    tdir.create("dir1/file1");
+    tdir.create("dir1/dir2/file2");
+    tdir.create("dir1/dir2/dir3/file3");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("dir1/dir2/file2"), op::CREATE, None),
+        (tdir.mkpath("dir1/dir2/file2"), op::CLOSE_WRITE, None),
+        (tdir.mkpath("dir1/dir2/dir3/file3"), op::CREATE, None),
+        (tdir.mkpath("dir1/dir2/dir3/file3"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+fn inotify_unwatch_inner_directory() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/dir2/dir3",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("dir1/dir2"), RecursiveMode::NonRecursive).expect("failed to watch directory");
+    watcher.unwatch(&tdir.mkpath("dir1/dir2")).expect("failed to unwatch directory");
+    assert!(watcher.unwatch(&tdir.mkpath("dir1/dir2")).is_err());
+
+    tdir.create("dir1/dir2/file1");
+    tdir.create("dir1/dir2/dir3/file2");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("dir1/dir2/file1"), op::CREATE, None),
+        (tdir.mkpath("dir1/dir2/file1"), op::CLOSE_WRITE, None),
+        (tdir.mkpath("dir1/dir2/dir3/file2"), op::CREATE, None),
+        (tdir.mkpath("dir1/dir2/dir3/file2"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+fn inotify_overlapping_ops() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/dir2/file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch_with(&tdir.mkpath("dir1/dir2"), WatchOptions {
+        ops: op::CHMOD,
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    // the outer watch still gets every operation in the inner directory
+    tdir.write("dir1/dir2/file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("dir1/dir2/file1"), op::WRITE, None),
+        (tdir.mkpath("dir1/dir2/file1"), op::CLOSE_WRITE, None),
+    ]);
+
+    // the flags only the outer watch needed are dropped
+    watcher.unwatch(&tdir.mkpath("dir1")).expect("failed to unwatch directory");
+
+    tdir.write("dir1/dir2/file1");
+    tdir.chmod("dir1/dir2/file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("dir1/dir2/file1"), op::CHMOD, None),
+    ]);
+}
+
+#[test]
+fn inotify_watch_again() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/dir2",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+    // watching a path again replaces its options
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::NonRecursive).expect("failed to watch directory");
+
+    tdir.create("dir1/dir2/file1");
+    tdir.create("dir1/file2");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("dir1/file2"), op::CREATE, None),
+        (tdir.mkpath("dir1/file2"), op::CLOSE_WRITE, None),
+    ]);
+}
@@ -0,0 +1,55 @@
+#![allow(dead_code)]
+
+//! Kept in its own test binary, so the watcher's threads are the only ones changing the count.
+
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::fs;
+use std::sync::mpsc;
+use tempdir::TempDir;
+
+use utils::*;
+
+#[cfg(target_os="linux")]
+fn thread_count() -> usize {
+    fs::read_dir("/proc/self/task").expect("failed to list threads").count()
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_move_out_bounded_threads() {
+    const FILES: usize = 500;
+
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let names: Vec<String> = (0..FILES).map(|i| format!("file{}", i)).collect();
+    tdir.create_all(names.iter().map(|n| &**n).collect());
+    fs::create_dir(tdir.mkpath("outside")).expect("failed to create directory");
+    fs::create_dir(tdir.mkpath("watched")).expect("failed to create directory");
+    for name in &names {
+        tdir.rename(name, &format!("watched/{}", name));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("watched"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    let threads = thread_count();
+    let mut max_threads = threads;
+    for name in &names {
+        // every move out of the watched directory leaves a rename pending
+        tdir.rename(&format!("watched/{}", name), &format!("outside/{}", name));
+        max_threads = std::cmp::max(max_threads, thread_count());
+    }
+
+    sleep(100);
+
+    let events: Vec<RawEvent> = rx.try_iter().collect();
+    assert_eq!(events.len(), FILES);
+    assert!(events.iter().all(|e| e.op.as_ref().ok() == Some(&op::REMOVE)));
+    assert_eq!(max_threads, threads);
+}
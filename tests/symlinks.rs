@@ -0,0 +1,170 @@
+#![cfg(unix)]
+
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::sync::mpsc;
+use tempdir::TempDir;
+
+use utils::*;
+
+fn symlinks(policy: SymlinkPolicy) -> WatchOptions {
+    WatchOptions {
+        symlinks: policy,
+        ..WatchOptions::default()
+    }
+}
+
+#[test]
+fn poll_symlink_follow_once() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/dir1",
+        "watch_dir/dir2",
+    ]);
+    symlink(tdir.mkpath("watch_dir/dir1"), tdir.mkpath("watch_dir/dir2/link")).expect("failed to create symlink");
+    symlink(tdir.mkpath("watch_dir"), tdir.mkpath("watch_dir/dir1/parent")).expect("failed to create symlink");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch_with(&tdir.mkpath("watch_dir"), symlinks(SymlinkPolicy::Follow)).expect("failed to watch directory");
+
+    tdir.create("watch_dir/dir1/file1");
+
+    sleep(100);
+
+    // the directory is only scanned through one of its paths
+    let actual = recv_events(&rx);
+    assert_eq!(actual.len(), 1);
+    assert!(actual[0] == (tdir.mkpath("watch_dir/dir1/file1"), op::CREATE, None) ||
+            actual[0] == (tdir.mkpath("watch_dir/dir2/link/file1"), op::CREATE, None));
+}
+
+#[test]
+fn poll_symlink_no_follow() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir",
+        "outside_dir",
+    ]);
+    symlink(tdir.mkpath("outside_dir"), tdir.mkpath("watch_dir/link")).expect("failed to create symlink");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch_with(&tdir.mkpath("watch_dir"), symlinks(SymlinkPolicy::NoFollow)).expect("failed to watch directory");
+
+    tdir.create("outside_dir/file1");
+    fs::remove_file(tdir.mkpath("watch_dir/link")).expect("failed to remove symlink");
+
+    sleep(100);
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("watch_dir/link"), op::REMOVE, None),
+    ]);
+}
+
+#[test]
+fn poll_symlink_confined() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/dir1/dir2",
+        "outside_dir",
+    ]);
+    symlink(tdir.mkpath("outside_dir"), tdir.mkpath("watch_dir/outside_link")).expect("failed to create symlink");
+    symlink(tdir.mkpath("watch_dir/dir1/dir2"), tdir.mkpath("watch_dir/inside_link")).expect("failed to create symlink");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch_with(&tdir.mkpath("watch_dir"), symlinks(SymlinkPolicy::Confined)).expect("failed to watch directory");
+
+    tdir.create("outside_dir/file1");
+
+    sleep(100);
+
+    assert_eq!(recv_events(&rx), vec![]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_symlink_confined() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/dir1",
+        "outside_dir",
+    ]);
+    symlink(tdir.mkpath("outside_dir"), tdir.mkpath("watch_dir/outside_link")).expect("failed to create symlink");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("watch_dir"), symlinks(SymlinkPolicy::Confined)).expect("failed to watch directory");
+
+    tdir.create("outside_dir/file1");
+    tdir.create("watch_dir/dir1/file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("watch_dir/dir1/file1"), op::CREATE, None),
+        (tdir.mkpath("watch_dir/dir1/file1"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_symlink_follow() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir",
+        "outside_dir",
+    ]);
+    symlink(tdir.mkpath("outside_dir"), tdir.mkpath("watch_dir/outside_link")).expect("failed to create symlink");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("watch_dir"), symlinks(SymlinkPolicy::Follow)).expect("failed to watch directory");
+
+    tdir.create("outside_dir/file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("watch_dir/outside_link/file1"), op::CREATE, None),
+        (tdir.mkpath("watch_dir/outside_link/file1"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_symlink_no_follow() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir",
+        "outside_dir",
+        "file1",
+    ]);
+    symlink(tdir.mkpath("outside_dir"), tdir.mkpath("watch_dir/outside_link")).expect("failed to create symlink");
+    symlink(tdir.mkpath("file1"), tdir.mkpath("file1_link")).expect("failed to create symlink");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("watch_dir"), symlinks(SymlinkPolicy::NoFollow)).expect("failed to watch directory");
+    // the link itself is watched, not the file it points to
+    watcher.watch_with(&tdir.mkpath("file1_link"), symlinks(SymlinkPolicy::NoFollow)).expect("failed to watch symlink");
+
+    tdir.create("outside_dir/file1");
+    tdir.write("file1");
+    tdir.remove("file1_link");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1_link"), op::CHMOD, None),
+        (tdir.mkpath("file1_link"), op::REMOVE, None),
+        (tdir.mkpath("file1_link"), op::WATCH_DROPPED, None),
+    ]);
+}
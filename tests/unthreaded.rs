@@ -0,0 +1,110 @@
+#![cfg(target_os="linux")]
+
+extern crate libc;
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::os::unix::io::AsRawFd;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tempdir::TempDir;
+
+use utils::*;
+
+/// Drive `watcher` the way an event loop would for `ms` milliseconds
+fn run_for(watcher: &mut UnthreadedINotifyWatcher, ms: u64) {
+    let end = Instant::now() + Duration::from_millis(ms);
+    loop {
+        let now = Instant::now();
+        if now >= end {
+            break;
+        }
+        let mut wait = end - now;
+        if let Some(timeout) = watcher.timeout() {
+            if timeout < wait {
+                wait = timeout;
+            }
+        }
+        let mut fd = libc::pollfd {
+            fd: watcher.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let wait_ms = wait.as_secs() as i32 * 1000 + (wait.subsec_nanos() / 1_000_000) as i32;
+        unsafe {
+            libc::poll(&mut fd, 1, wait_ms);
+        }
+        watcher.process_events();
+    }
+}
+
+#[test]
+fn unthreaded_inotify_new_directory() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: UnthreadedINotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.create("dir1");
+    run_for(&mut watcher, 10);
+    tdir.create("dir1/file1");
+    run_for(&mut watcher, 10);
+
+    // nothing is reported without calling `process_events`
+    tdir.create("file2");
+    sleep(10);
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("dir1"), op::CREATE, None),
+        (tdir.mkpath("dir1/file1"), op::CREATE, None),
+        (tdir.mkpath("dir1/file1"), op::CLOSE_WRITE, None),
+    ]);
+
+    run_for(&mut watcher, 10);
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file2"), op::CREATE, None),
+        (tdir.mkpath("file2"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+fn unthreaded_inotify_rename() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/file1",
+        "watch_dir/file2",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: UnthreadedINotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+    assert_eq!(watcher.timeout(), None);
+
+    tdir.rename("watch_dir/file1", "watch_dir/file1_renamed");
+    run_for(&mut watcher, 50);
+
+    let actual = recv_events(&rx);
+    let cookies = extract_cookies(&actual);
+    assert_eq!(cookies.len(), 1);
+    assert_eq!(actual, vec![
+        (tdir.mkpath("watch_dir/file1"), op::RENAME, Some(cookies[0])),
+        (tdir.mkpath("watch_dir/file1_renamed"), op::RENAME, Some(cookies[0])),
+    ]);
+
+    // the first half of a rename out of the watched directory is reported once its window elapsed
+    tdir.rename("watch_dir/file2", "file2");
+    sleep(10);
+    watcher.process_events();
+    assert!(watcher.timeout().is_some());
+    assert_eq!(recv_events(&rx), vec![]);
+
+    run_for(&mut watcher, 50);
+    assert_eq!(watcher.timeout(), None);
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("watch_dir/file2"), op::REMOVE, None),
+    ]);
+}
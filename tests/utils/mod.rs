@@ -23,7 +23,7 @@ pub fn recv_events_with_timeout(rx: &Receiver<RawEvent>, timeout: Duration) ->
 
     while start.elapsed() < timeout {
         match rx.try_recv() {
-            Ok(RawEvent{path: Some(path), op: Ok(op), cookie}) => {
+            Ok(RawEvent{path: Some(path), op: Ok(op), cookie, ..}) => {
                 evs.push((path, op, cookie));
             },
             Ok(RawEvent{path: None, ..})  => (),
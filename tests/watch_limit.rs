@@ -0,0 +1,181 @@
+#![allow(dead_code)]
+
+//! Kept in its own test binary, because lowering the inotify watch limit affects every watcher of
+//! the user.
+
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tempdir::TempDir;
+
+use utils::*;
+
+const MAX_USER_WATCHES: &str = "/proc/sys/fs/inotify/max_user_watches";
+
+static LIMIT: Mutex<()> = Mutex::new(());
+
+fn read_limit() -> String {
+    let mut limit = String::new();
+    fs::File::open(MAX_USER_WATCHES)
+        .and_then(|mut f| f.read_to_string(&mut limit))
+        .expect("failed to read the watch limit");
+    limit
+}
+
+fn write_limit(limit: &str) {
+    fs::File::create(MAX_USER_WATCHES)
+        .and_then(|mut f| f.write_all(limit.as_bytes()))
+        .expect("failed to set the watch limit");
+}
+
+/// Count the inotify watches in `/proc/<pid>/fdinfo/*` for every process matching `pid`
+fn count_watches(pid: &str) -> usize {
+    let mut count = 0;
+    for process in fs::read_dir("/proc").expect("failed to list processes").filter_map(|e| e.ok()) {
+        if pid != "*" && process.file_name().to_str() != Some(pid) {
+            continue;
+        }
+        let fds = match fs::read_dir(process.path().join("fdinfo")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+        for fd in fds.filter_map(|e| e.ok()) {
+            let mut info = String::new();
+            if fs::File::open(fd.path()).and_then(|mut f| f.read_to_string(&mut info)).is_ok() {
+                count += info.lines().filter(|l| l.starts_with("inotify wd:")).count();
+            }
+        }
+    }
+    count
+}
+
+/// Run `f` while only `available` more inotify watches can be added
+fn with_watch_limit<F: FnOnce()>(available: usize, f: F) {
+    struct Restore(String);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            write_limit(&self.0);
+        }
+    }
+
+    let _lock = LIMIT.lock().unwrap_or_else(|e| e.into_inner());
+    let _restore = Restore(read_limit());
+    write_limit(&format!("{}", count_watches("*") + available));
+    f();
+}
+
+fn create_tree(tdir: &TempDir, dirs: usize) -> Vec<String> {
+    let names: Vec<String> = (0..dirs).map(|i| format!("dir{}", i)).collect();
+    tdir.create_all(names.iter().map(|n| &**n).collect());
+    names
+}
+
+#[test]
+#[cfg(target_os="linux")]
+#[ignore] // changes fs.inotify.max_user_watches, which requires root
+fn inotify_watch_limit_without_fallback() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+    create_tree(&tdir, 20);
+
+    with_watch_limit(5, || {
+        let (tx, _rx) = mpsc::channel();
+        let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+        let err = watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive)
+            .expect_err("watching more directories than the limit should fail");
+        match err.kind {
+            ErrorKind::MaxFilesWatch => {}
+            _ => panic!("unexpected error: {:?}", err),
+        }
+
+        // the directories watched before reaching the limit are unwatched again
+        sleep(10);
+        assert_eq!(count_watches("self"), 0);
+    });
+}
+
+#[test]
+#[cfg(target_os="linux")]
+#[ignore] // changes fs.inotify.max_user_watches, which requires root
+fn inotify_watch_limit_poll_fallback() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+    let dirs = create_tree(&tdir, 20);
+
+    with_watch_limit(5, || {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher: INotifyWatcher = WatcherBuilder::new()
+            .poll_fallback(Duration::from_millis(50))
+            .build_raw(tx)
+            .expect("failed to create inotify watcher");
+        watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+        let polled: Vec<PathBuf> = rx.try_iter()
+            .map(|e| match e {
+                RawEvent { path: Some(path), op: Err(Error { kind: ErrorKind::MaxFilesWatch, .. }), .. } => path,
+                e => panic!("unexpected event: {:?}", e),
+            })
+            .collect();
+        assert!(!polled.is_empty());
+        assert!(polled.len() < dirs.len());
+
+        for dir in &dirs {
+            tdir.create(&format!("{}/file1", dir));
+        }
+
+        sleep(200);
+
+        let mut actual: Vec<PathBuf> = rx.try_iter()
+            .filter_map(|e| match e {
+                RawEvent { path: Some(path), op: Ok(op::CREATE), .. } => Some(path),
+                _ => None,
+            })
+            .collect();
+        actual.sort();
+        let mut expected: Vec<PathBuf> = dirs.iter().map(|d| tdir.mkpath(&format!("{}/file1", d))).collect();
+        expected.sort();
+        assert_eq!(actual, expected);
+    });
+}
+
+#[test]
+#[cfg(target_os="linux")]
+#[ignore] // changes fs.inotify.max_user_watches, which requires root
+fn inotify_poll_fallback_unwatch_inner_root() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+    create_tree(&tdir, 2);
+
+    // only the top directory gets an inotify watch, the others are polled
+    with_watch_limit(1, || {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher: INotifyWatcher = WatcherBuilder::new()
+            .poll_fallback(Duration::from_millis(50))
+            .build_raw(tx)
+            .expect("failed to create inotify watcher");
+        watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+        watcher.watch(&tdir.mkpath("dir0"), RecursiveMode::Recursive).expect("failed to watch directory");
+        // dir0 is polled for the outer watch again
+        watcher.unwatch(&tdir.mkpath("dir0")).expect("failed to unwatch directory");
+        rx.try_iter().count();
+
+        tdir.create("dir0/file1");
+        tdir.create("dir1/file1");
+
+        sleep(200);
+
+        let mut actual: Vec<PathBuf> = rx.try_iter()
+            .filter_map(|e| match e {
+                RawEvent { path: Some(path), op: Ok(op::CREATE), .. } => Some(path),
+                _ => None,
+            })
+            .collect();
+        actual.sort();
+        assert_eq!(actual, vec![tdir.mkpath("dir0/file1"), tdir.mkpath("dir1/file1")]);
+    });
+}
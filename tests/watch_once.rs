@@ -0,0 +1,154 @@
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::sync::mpsc;
+use std::time::Duration;
+use tempdir::TempDir;
+
+use utils::*;
+
+/// Number of watches of the inotify instance behind `fd`
+#[cfg(target_os="linux")]
+fn count_watches(fd: i32) -> usize {
+    use std::fs::File;
+    use std::io::Read;
+
+    let mut info = String::new();
+    File::open(format!("/proc/self/fdinfo/{}", fd))
+        .and_then(|mut f| f.read_to_string(&mut info))
+        .expect("failed to read fdinfo");
+    info.lines().filter(|l| l.starts_with("inotify wd:")).count()
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_watch_once_file() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_once(&tdir.mkpath("file1"), RecursiveMode::NonRecursive).expect("failed to watch file");
+
+    tdir.write("file1");
+    tdir.write("file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::WRITE, None),
+    ]);
+
+    // the watch was removed
+    assert!(watcher.unwatch(&tdir.mkpath("file1")).is_err());
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_watch_once_recursive() {
+    use std::os::unix::io::AsRawFd;
+
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/dir1/dir2",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: UnthreadedINotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_once(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+    assert_eq!(count_watches(watcher.as_raw_fd()), 3);
+
+    tdir.create("watch_dir/dir1/dir2/file1");
+    tdir.create("watch_dir/file2");
+    watcher.process_events();
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("watch_dir/dir1/dir2/file1"), op::CREATE, None),
+    ]);
+    assert_eq!(count_watches(watcher.as_raw_fd()), 0);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_watch_once_rename() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_once(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.rename("watch_dir/file1", "watch_dir/file2");
+    tdir.create("watch_dir/file3");
+
+    // both halves of the rename are delivered
+    let actual = recv_events(&rx);
+    let cookies = extract_cookies(&actual);
+    assert_eq!(cookies.len(), 1);
+    assert_eq!(actual, vec![
+        (tdir.mkpath("watch_dir/file1"), op::RENAME, Some(cookies[0])),
+        (tdir.mkpath("watch_dir/file2"), op::RENAME, Some(cookies[0])),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_watch_once_shared() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir/file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_once(&tdir.mkpath("watch_dir/file1"), RecursiveMode::NonRecursive).expect("failed to watch file");
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.chmod("watch_dir/file1");
+    tdir.chmod("watch_dir/file1");
+
+    // the first change is reported by both watches, the directory is still watched after the
+    // one-shot watch ended
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("watch_dir/file1"), op::CHMOD, None),
+        (tdir.mkpath("watch_dir/file1"), op::CHMOD, None),
+        (tdir.mkpath("watch_dir/file1"), op::CHMOD, None),
+    ]);
+}
+
+#[test]
+fn poll_watch_once() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "watch_dir",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch_once(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.create("watch_dir/file1");
+
+    // wait for the scan reporting the first change, however slow it is
+    let event = rx.recv_timeout(Duration::from_secs(5)).expect("failed to receive event");
+    assert_eq!(event.path, Some(tdir.mkpath("watch_dir/file1")));
+    assert_eq!(event.op.expect("unexpected error event"), op::CREATE);
+
+    tdir.create("watch_dir/file2");
+    tdir.remove("watch_dir/file1");
+
+    sleep(100);
+
+    assert_eq!(recv_events(&rx), vec![]);
+    assert!(watcher.unwatch(&tdir.mkpath("watch_dir")).is_err());
+}
@@ -0,0 +1,125 @@
+extern crate notify;
+extern crate tempdir;
+
+mod utils;
+
+use notify::*;
+use std::sync::mpsc;
+use tempdir::TempDir;
+
+use utils::*;
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_close_write_only() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("."), WatchOptions {
+        ops: op::CLOSE_WRITE,
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    tdir.write("file1");
+    tdir.chmod("file1");
+    tdir.create("dir1");
+
+    sleep(10);
+
+    tdir.create("dir1/file2");
+    tdir.rename("dir1/file2", "dir1/file3");
+    tdir.remove("file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::CLOSE_WRITE, None),
+        (tdir.mkpath("dir1/file2"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_without_chmod() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("."), WatchOptions {
+        ops: WatchOptions::default_ops() - op::CHMOD,
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    tdir.create("file1");
+    tdir.chmod("file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::CREATE, None),
+        (tdir.mkpath("file1"), op::CLOSE_WRITE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_rename_out_without_remove() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/file1",
+        "dir2",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch_with(&tdir.mkpath("dir1"), WatchOptions {
+        ops: op::RENAME,
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    tdir.rename("dir1/file1", "dir2/file1");
+
+    sleep(20);
+
+    assert_eq!(recv_events(&rx), vec![]);
+}
+
+#[test]
+fn poll_create_only() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+        "file2",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch_with(&tdir.mkpath("."), WatchOptions {
+        ops: op::CREATE,
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+
+    tdir.write("file1");
+    tdir.remove("file2");
+    tdir.create("file3");
+
+    sleep(100);
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file3"), op::CREATE, None),
+    ]);
+}
+
+#[test]
+fn null_accepts_ops() {
+    let mut watcher = NullWatcher;
+    watcher.watch_with(std::path::Path::new("/"), WatchOptions {
+        ops: op::CLOSE_WRITE,
+        ..WatchOptions::default()
+    }).expect("failed to watch directory");
+}
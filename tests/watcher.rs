@@ -1,5 +1,8 @@
+extern crate filetime;
 extern crate notify;
 extern crate tempdir;
+#[cfg(feature = "stream")]
+extern crate futures;
 
 mod utils;
 
@@ -8,9 +11,14 @@ use std::sync::mpsc;
 use tempdir::TempDir;
 use std::thread;
 use std::env;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 #[cfg(all(feature = "manual_tests", target_os="linux"))]
-use std::time::{Duration, Instant};
+use std::time::Instant;
 #[cfg(all(feature = "manual_tests", target_os="linux"))]
 use std::io::prelude::*;
 #[cfg(all(feature = "manual_tests", target_os="linux"))]
@@ -18,6 +26,15 @@ use std::fs::File;
 
 use utils::*;
 
+#[cfg(feature = "stream")]
+use futures::channel::oneshot;
+#[cfg(feature = "stream")]
+use futures::executor::block_on;
+#[cfg(feature = "stream")]
+use futures::future::{self, Either};
+#[cfg(feature = "stream")]
+use futures::stream::StreamExt;
+
 const NETWORK_PATH: &'static str = ""; // eg.: \\\\MY-PC\\Users\\MyName
 
 #[cfg(target_os="linux")]
@@ -57,6 +74,137 @@ fn new_recommended() {
     assert!(w.is_ok());
 }
 
+#[test]
+fn new_closure() {
+    let w: Result<RecommendedWatcher> = Watcher::new_raw(|_: RawEvent| ());
+    assert!(w.is_ok());
+
+    let w: Result<RecommendedWatcher> = Watcher::new(|_: DebouncedEvent| (), Duration::from_secs(1));
+    assert!(w.is_ok());
+}
+
+#[test]
+fn watch_closure_handler() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let handler_events = events.clone();
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(move |event: RawEvent| {
+        if let RawEvent { path: Some(path), op: Ok(op), .. } = event {
+            handler_events.lock().unwrap().push((path, op));
+        }
+    }).expect("failed to create recommended watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep_windows(100);
+
+    tdir.create("file1");
+
+    sleep(500);
+
+    let events = events.lock().unwrap();
+    assert!(events.contains(&(tdir.mkpath("file1"), op::CREATE)));
+}
+
+#[test]
+fn watch_panicking_handler() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let handler_events = events.clone();
+    let mut is_first = true;
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(move |event: RawEvent| {
+        if is_first {
+            is_first = false;
+            panic!("handler panicked");
+        }
+        if let RawEvent { path: Some(path), op: Ok(op), .. } = event {
+            handler_events.lock().unwrap().push((path, op));
+        }
+    }).expect("failed to create recommended watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep_windows(100);
+
+    tdir.create("file1");
+
+    sleep(500);
+
+    // the watcher keeps running after the panic
+    tdir.create("file2");
+
+    sleep(500);
+
+    assert!(events.lock().unwrap().contains(&(tdir.mkpath("file2"), op::CREATE)));
+    watcher.unwatch(&tdir.mkpath(".")).expect("failed to unwatch directory");
+}
+
+struct CountingHandler(Arc<AtomicUsize>);
+
+impl EventHandler<RawEvent> for CountingHandler {
+    fn handle_event(&mut self, _: RawEvent) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn watch_custom_handler() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(CountingHandler(count.clone())).expect("failed to create recommended watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep_windows(100);
+
+    tdir.create("file1");
+
+    sleep(500);
+
+    assert!(count.load(Ordering::SeqCst) > 0);
+}
+
+#[test]
+fn watch_boxed_watchers() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1",
+        "dir2",
+    ]);
+
+    sleep_macos(10);
+
+    let (tx1, rx1) = mpsc::channel();
+    let (tx2, rx2) = mpsc::channel();
+    let mut watchers: Vec<Box<dyn Watcher>> = vec![
+        Box::new(RecommendedWatcher::new_raw(tx1).expect("failed to create recommended watcher")),
+        Box::new(PollWatcher::with_delay_ms(tx2, 50).expect("failed to create poll watcher")),
+    ];
+    watchers[0].watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watchers[1].watch_path(tdir.mkpath("dir2"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+
+    tdir.create("dir1/file1");
+    tdir.create("dir2/file1");
+
+    sleep(100);
+
+    assert!(recv_events(&rx1).contains(&(tdir.mkpath("dir1/file1"), op::CREATE, None)));
+    assert!(recv_events(&rx2).contains(&(tdir.mkpath("dir2/file1"), op::CREATE, None)));
+
+    for watcher in &mut watchers {
+        watcher.unwatch_path(tdir.mkpath(".")).expect_err("unwatched a path that isn't watched");
+    }
+}
+
 // if this test builds, it means RecommendedWatcher is Send.
 #[test]
 fn test_watcher_send() {
@@ -65,7 +213,7 @@ fn test_watcher_send() {
     let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).unwrap();
 
     thread::spawn(move || {
-        watcher.watch(".", RecursiveMode::Recursive).unwrap();
+        watcher.watch_path(".", RecursiveMode::Recursive).unwrap();
     }).join().unwrap();
 }
 
@@ -81,7 +229,7 @@ fn test_watcher_sync() {
 
     thread::spawn(move || {
         let mut watcher = watcher.write().unwrap();
-        watcher.watch(".", RecursiveMode::Recursive).unwrap();
+        watcher.watch_path(".", RecursiveMode::Recursive).unwrap();
     }).join().unwrap();
 }
 
@@ -96,13 +244,13 @@ fn watch_relative() {
 
         let (tx, _) = mpsc::channel();
         let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-        watcher.watch("dir1", RecursiveMode::Recursive).expect("failed to watch directory");
+        watcher.watch_path("dir1", RecursiveMode::Recursive).expect("failed to watch directory");
 
-        watcher.unwatch("dir1").expect("failed to unwatch directory");
+        watcher.unwatch_path("dir1").expect("failed to unwatch directory");
 
         if cfg!(not(target_os="windows")) {
-            match watcher.unwatch("dir1") {
-                Err(Error::WatchNotFound) => (),
+            match watcher.unwatch_path("dir1") {
+                Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
                 Err(e) => panic!("{:?}", e),
                 Ok(o) => panic!("{:?}", o),
             }
@@ -116,13 +264,13 @@ fn watch_relative() {
 
         let (tx, _) = mpsc::channel();
         let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-        watcher.watch("file1", RecursiveMode::Recursive).expect("failed to watch file");
+        watcher.watch_path("file1", RecursiveMode::Recursive).expect("failed to watch file");
 
-        watcher.unwatch("file1").expect("failed to unwatch file");
+        watcher.unwatch_path("file1").expect("failed to unwatch file");
 
         if cfg!(not(target_os="windows")) {
-            match watcher.unwatch("file1") {
-                Err(Error::WatchNotFound) => (),
+            match watcher.unwatch_path("file1") {
+                Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
                 Err(e) => panic!("{:?}", e),
                 Ok(o) => panic!("{:?}", o),
             }
@@ -137,13 +285,13 @@ fn watch_relative() {
 
         let (tx, _) = mpsc::channel();
         let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-        watcher.watch("dir1", RecursiveMode::Recursive).expect("failed to watch directory");
+        watcher.watch_path("dir1", RecursiveMode::Recursive).expect("failed to watch directory");
 
-        watcher.unwatch("dir1").expect("failed to unwatch directory");
+        watcher.unwatch_path("dir1").expect("failed to unwatch directory");
 
         if cfg!(not(target_os="windows")) {
-            match watcher.unwatch("dir1") {
-                Err(Error::WatchNotFound) => (),
+            match watcher.unwatch_path("dir1") {
+                Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
                 Err(e) => panic!("{:?}", e),
                 Ok(o) => panic!("{:?}", o),
             }
@@ -158,13 +306,13 @@ fn watch_relative() {
 
         let (tx, _) = mpsc::channel();
         let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-        watcher.watch("file1", RecursiveMode::Recursive).expect("failed to watch file");
+        watcher.watch_path("file1", RecursiveMode::Recursive).expect("failed to watch file");
 
-        watcher.unwatch("file1").expect("failed to unwatch file");
+        watcher.unwatch_path("file1").expect("failed to unwatch file");
 
         if cfg!(not(target_os="windows")) {
-            match watcher.unwatch("file1") {
-                Err(Error::WatchNotFound) => (),
+            match watcher.unwatch_path("file1") {
+                Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
                 Err(e) => panic!("{:?}", e),
                 Ok(o) => panic!("{:?}", o),
             }
@@ -184,13 +332,13 @@ fn watch_absolute_network_directory() {
 
     let (tx, _) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-    watcher.watch(tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
 
-    watcher.unwatch(tdir.mkpath("dir1")).expect("failed to unwatch directory");
+    watcher.unwatch(&tdir.mkpath("dir1")).expect("failed to unwatch directory");
 
     if cfg!(not(target_os="windows")) {
-        match watcher.unwatch(tdir.mkpath("dir1")) {
-            Err(Error::WatchNotFound) => (),
+        match watcher.unwatch(&tdir.mkpath("dir1")) {
+            Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
             Err(e) => panic!("{:?}", e),
             Ok(o) => panic!("{:?}", o),
         }
@@ -211,13 +359,13 @@ fn watch_absolute_network_file() {
 
     let (tx, _) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-    watcher.watch(tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
 
-    watcher.unwatch(tdir.mkpath("file1")).expect("failed to unwatch file");
+    watcher.unwatch(&tdir.mkpath("file1")).expect("failed to unwatch file");
 
     if cfg!(not(target_os="windows")) {
-        match watcher.unwatch(tdir.mkpath("file1")) {
-            Err(Error::WatchNotFound) => (),
+        match watcher.unwatch(&tdir.mkpath("file1")) {
+            Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
             Err(e) => panic!("{:?}", e),
             Ok(o) => panic!("{:?}", o),
         }
@@ -239,7 +387,7 @@ fn inotify_queue_overflow() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     for i in 0..20 {
         let filename = format!("file{}", i);
@@ -315,6 +463,38 @@ fn watch_recursive_create_directory() {
     }
 }
 
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_watch_recursive_create_directory_tree() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    // the sub-directories and the file are created before the watches are installed, or
+    // right after, both must be reported exactly once
+    tdir.create("dir1/dir2/dir3/file1");
+
+    let mut actual = recv_events(&rx);
+    actual.retain(|&(_, op, _)| op == op::CREATE);
+    actual.sort_by(|a, b| a.0.cmp(&b.0));
+
+    assert_eq!(actual, vec![
+        (tdir.mkpath("dir1"), op::CREATE, None),
+        (tdir.mkpath("dir1/dir2"), op::CREATE, None),
+        (tdir.mkpath("dir1/dir2/dir3"), op::CREATE, None),
+        (tdir.mkpath("dir1/dir2/dir3/file1"), op::CREATE, None),
+    ]);
+
+    tdir.create("dir1/dir2/dir3/file2");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("dir1/dir2/dir3/file2"), op::CREATE, None),
+        (tdir.mkpath("dir1/dir2/dir3/file2"), op::CLOSE_WRITE, None),
+    ]);
+}
+
 #[test]
 fn watch_recursive_move() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
@@ -327,7 +507,7 @@ fn watch_recursive_move() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep_windows(100);
 
@@ -421,6 +601,7 @@ fn watch_recursive_move_in() {
     } else if cfg!(target_os="linux") {
         assert_eq!(actual, vec![
             (tdir.mkpath("watch_dir/dir1b"), op::CREATE, None),
+            (tdir.mkpath("watch_dir/dir1b/dir1"), op::CREATE, None), // reported by scanning dir1b
             (tdir.mkpath("watch_dir/dir1b/dir1/file1"), op::CREATE, None),
             (tdir.mkpath("watch_dir/dir1b/dir1/file1"), op::CLOSE_WRITE, None),
         ]);
@@ -445,7 +626,7 @@ fn watch_recursive_move_out() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-    watcher.watch(tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep_windows(100);
 
@@ -497,7 +678,7 @@ fn watch_nonrecursive() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::NonRecursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::NonRecursive).expect("failed to watch directory");
 
     sleep_windows(100);
 
@@ -535,7 +716,7 @@ fn watch_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-    watcher.watch(tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep_windows(100);
 
@@ -564,7 +745,7 @@ fn poll_watch_recursive_create_directory() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep(1100); // PollWatcher has only a resolution of 1 second
 
@@ -572,7 +753,7 @@ fn poll_watch_recursive_create_directory() {
 
     sleep(1100); // PollWatcher has only a resolution of 1 second
 
-    watcher.unwatch(tdir.mkpath(".")).expect("failed to unwatch directory");
+    watcher.unwatch(&tdir.mkpath(".")).expect("failed to unwatch directory");
 
     tdir.create("dir1/file2");
 
@@ -597,7 +778,7 @@ fn poll_watch_recursive_move() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep(1100); // PollWatcher has only a resolution of 1 second
 
@@ -630,12 +811,14 @@ fn poll_watch_recursive_move() {
             (tdir.mkpath("dir1b/file2"), op::CREATE, None),
         ]);
     } else {
+        // the directory is matched by its inode, its contents aren't reported separately
+        let cookie = actual[2].2;
+        assert!(cookie.is_some());
         assert_eq!(actual, vec![
             (tdir.mkpath("."), op::WRITE, None), // parent directory gets modified
-            (tdir.mkpath("dir1a"), op::REMOVE, None),
-            (tdir.mkpath("dir1a/file1"), op::REMOVE, None),
-            (tdir.mkpath("dir1b"), op::CREATE, None),
-            (tdir.mkpath("dir1b/file1"), op::CREATE, None),
+            (tdir.mkpath("dir1a"), op::WRITE, None), // modified before it was found renamed
+            (tdir.mkpath("dir1a"), op::RENAME, cookie),
+            (tdir.mkpath("dir1b"), op::RENAME, cookie),
             (tdir.mkpath("dir1b/file2"), op::CREATE, None),
         ]);
     }
@@ -653,7 +836,7 @@ fn poll_watch_recursive_move_in() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
-    watcher.watch(tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep(1100); // PollWatcher has only a resolution of 1 second
 
@@ -692,7 +875,7 @@ fn poll_watch_recursive_move_out() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
-    watcher.watch(tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("watch_dir"), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep(1100); // PollWatcher has only a resolution of 1 second
 
@@ -732,7 +915,7 @@ fn poll_watch_nonrecursive() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
-    watcher.watch(tdir.mkpath("."), RecursiveMode::NonRecursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::NonRecursive).expect("failed to watch directory");
 
     sleep(1100); // PollWatcher has only a resolution of 1 second
 
@@ -763,7 +946,7 @@ fn poll_watch_file() {
 
     let (tx, rx) = mpsc::channel();
     let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
-    watcher.watch(tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch directory");
 
     sleep(1100); // PollWatcher has only a resolution of 1 second
 
@@ -776,171 +959,731 @@ fn poll_watch_file() {
 }
 
 #[test]
-fn watch_nonexisting() {
-    let tdir1 = TempDir::new("temp_dir1").expect("failed to create temporary directory");
-    let tdir2 = TempDir::new("temp_dir2").expect("failed to create temporary directory");
+fn poll_watch_file_back_to_back_writes() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
-    sleep_macos(10);
+    tdir.create_all(vec![
+        "file1",
+    ]);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-    watcher.watch(&tdir1.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
-    let result = watcher.watch(&tdir2.mkpath("non_existing"), RecursiveMode::Recursive);
-    assert!(result.is_err());
-
-    // make sure notify is still working
+    let mut watcher = PollWatcher::with_delay_ms(tx, 20).expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
 
-    sleep_windows(100);
+    sleep(50);
 
-    tdir1.create("file1");
+    // all within the same second, the last writes don't change the size
+    tdir.write("file1");
+    sleep(50);
+    tdir.write("file1");
+    sleep(50);
+    tdir.write("file1");
 
-    if cfg!(target_os="macos") {
-        assert_eq!(inflate_events(recv_events(&rx)), vec![
-            (tdir1.mkpath("file1"), op::CREATE, None),
-        ]);
-    } else if cfg!(target_os="windows") {
-        assert_eq!(recv_events(&rx), vec![
-            (tdir1.mkpath("file1"), op::CREATE, None)
-        ]);
-    } else {
-        assert_eq!(recv_events(&rx), vec![
-            (tdir1.mkpath("file1"), op::CREATE, None),
-            (tdir1.mkpath("file1"), op::CLOSE_WRITE, None)
-        ]);
-    }
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::WRITE, None),
+        (tdir.mkpath("file1"), op::WRITE, None),
+        (tdir.mkpath("file1"), op::WRITE, None),
+    ]);
 }
 
 #[test]
-fn unwatch_file() {
+#[cfg(unix)]
+fn poll_watch_file_preserved_mtime() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
         "file1",
     ]);
+    tdir.write("file1");
 
-    sleep_macos(10);
-
-    let (tx, _) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
     watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
 
-    match watcher.unwatch(&tdir.mkpath("file1")) {
-        Ok(_) => (),
-        Err(e) => panic!("{:?}", e),
-    }
+    sleep(100);
+
+    // rewrite the file with the same size and restore its times, like `touch -r` or `rsync` do,
+    // which is only visible as an attribute change, just like inotify reports it last
+    write_preserving_mtime(&tdir.mkpath("file1"), b"more data");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::CHMOD, None),
+    ]);
 }
 
 #[test]
-fn unwatch_directory() {
+fn poll_watch_recursive_chmod() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
-        "dir1",
+        "dir1/file1",
     ]);
 
-    sleep_macos(10);
-
-    let (tx, _) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
-    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
-
-    match watcher.unwatch(&tdir.mkpath("dir1")) {
-        Ok(_) => (),
-        Err(e) => panic!("{:?}", e),
-    }
-}
-
-#[test]
-#[cfg(not(target_os="windows"))]
-fn unwatch_nonexisting() {
-    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
 
-    sleep_macos(10);
+    sleep(100);
 
-    let (tx, _) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+    tdir.chmod("dir1/file1");
+    sleep(100);
+    tdir.chmod("dir1");
 
-    match watcher.unwatch(&tdir.mkpath("file1")) {
-        Err(Error::WatchNotFound) => (),
-        Err(e) => panic!("{:?}", e),
-        Ok(o) => panic!("{:?}", o),
-    }
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("dir1/file1"), op::CHMOD, None),
+        (tdir.mkpath("dir1"), op::CHMOD, None),
+    ]);
 }
 
 #[test]
-fn self_delete_file() {
+fn poll_watch_file_write_chmod() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
         "file1",
     ]);
 
-    sleep_macos(10);
-
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+    let mut watcher = PollWatcher::with_delay_ms(tx, 500).expect("failed to create poll watcher");
     watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
 
-    sleep_windows(100);
-
-    tdir.remove("file1");
-
-    let actual = if cfg!(target_os="macos") {
-        inflate_events(recv_events(&rx))
-    } else {
-        recv_events(&rx)
-    };
-
-    if cfg!(target_os="windows") {
-        assert_eq!(actual, vec![
-            (tdir.mkpath("file1"), op::REMOVE, None),
-        ]);
-    } else if cfg!(target_os="macos") {
-        assert_eq!(actual, vec![
-            (tdir.mkpath("file1"), op::CREATE | op::REMOVE, None),
-        ]);
-    } else {
-        assert_eq!(actual, vec![
-            (tdir.mkpath("file1"), op::CHMOD, None),
-            (tdir.mkpath("file1"), op::REMOVE, None),
-        ]);
-    }
-
-    if cfg!(not(any(target_os="windows", target_os="macos"))) {
-        tdir.create("file1");
+    // both changes are found by the same scan
+    tdir.write("file1");
+    tdir.chmod("file1");
 
-        assert_eq!(recv_events(&rx), vec![]);
+    sleep(600);
 
-        match watcher.unwatch(&tdir.mkpath("file1")) {
-            Err(Error::WatchNotFound) => (),
-            Err(e) => panic!("{:?}", e),
-            Ok(o) => panic!("{:?}", o),
-        }
-    }
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::WRITE, None),
+        (tdir.mkpath("file1"), op::CHMOD, None),
+    ]);
 }
 
 #[test]
-#[cfg(not(target_os="windows"))]
-fn self_delete_directory() {
+#[cfg(unix)]
+fn poll_watch_rename() {
     let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
 
     tdir.create_all(vec![
         "dir1",
+        "file1a",
     ]);
 
-    sleep_macos(10);
-
     let (tx, rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+    let mut watcher = PollWatcher::with_delay_ms(tx, 50).expect("failed to create poll watcher");
     watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
 
-    sleep_windows(100);
+    sleep(1100); // PollWatcher has only a resolution of 1 second
 
-    tdir.remove("dir1");
+    tdir.rename("file1a", "dir1/file1a");
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+    tdir.rename("dir1/file1a", "dir1/file1b");
 
-    let actual = if cfg!(target_os="macos") {
-        inflate_events(recv_events(&rx))
-    } else {
+    let actual = recv_events(&rx);
+    let cookie = actual[3].2;
+    assert!(cookie.is_some());
+    assert_eq!(actual, vec![
+        (tdir.mkpath("dir1"), op::WRITE, None), // parent directory gets modified
+        (tdir.mkpath("dir1/file1a"), op::CREATE, None),
+        (tdir.mkpath("dir1"), op::WRITE, None),
+        (tdir.mkpath("dir1/file1a"), op::RENAME, cookie),
+        (tdir.mkpath("dir1/file1b"), op::RENAME, cookie),
+    ]);
+}
+
+/// Overwrite the start of `path` with `contents` and restore its modification time
+#[cfg(unix)]
+fn write_preserving_mtime(path: &Path, contents: &[u8]) {
+    use filetime::{set_file_times, FileTime};
+    use std::io::Write;
+
+    let metadata = fs::metadata(path).expect("failed to get metadata");
+    fs::OpenOptions::new().write(true).open(path).expect("failed to open file")
+        .write_all(contents).expect("failed to write to file");
+    set_file_times(path,
+                   FileTime::from_last_access_time(&metadata),
+                   FileTime::from_last_modification_time(&metadata))
+        .expect("failed to set file times");
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_content_hash() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+        "file2",
+    ]);
+    fs::write(tdir.mkpath("file1"), b"aaaa").expect("failed to write to file");
+    fs::write(tdir.mkpath("file2"), b"aaaaaaaa").expect("failed to write to file");
+
+    let (tx, rx) = mpsc::channel();
+    let builder = WatcherBuilder::new()
+        .poll_interval(Duration::from_millis(50))
+        .content_hash(ContentHash {
+            max_size: Some(4),
+            ..ContentHash::default()
+        });
+    let mut watcher: PollWatcher = builder.build_raw(tx).expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep(100);
+
+    // same size and modification time, only the second file is too large to be hashed
+    write_preserving_mtime(&tdir.mkpath("file1"), b"bbbb");
+    write_preserving_mtime(&tdir.mkpath("file2"), b"bbbbbbbb");
+
+    let mut actual = recv_events(&rx);
+    actual.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(actual, vec![
+        (tdir.mkpath("file1"), op::WRITE, None),
+        (tdir.mkpath("file2"), op::CHMOD, None),
+    ]);
+
+    // a new modification time without new contents
+    filetime::set_file_times(tdir.mkpath("file1"),
+                             filetime::FileTime::from_seconds_since_1970(1000, 0),
+                             filetime::FileTime::from_seconds_since_1970(1000, 0))
+        .expect("failed to set file times");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::CHMOD, None),
+    ]);
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_content_hash_only_if_unchanged() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+    fs::write(tdir.mkpath("file1"), b"aaaa").expect("failed to write to file");
+
+    let (tx, rx) = mpsc::channel();
+    let builder = WatcherBuilder::new()
+        .poll_interval(Duration::from_millis(50))
+        .content_hash(ContentHash {
+            only_if_unchanged: true,
+            ..ContentHash::default()
+        });
+    let mut watcher: PollWatcher = builder.build_raw(tx).expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
+
+    sleep(100);
+
+    write_preserving_mtime(&tdir.mkpath("file1"), b"bbbb");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::WRITE, None),
+    ]);
+
+    // the modification time is trusted
+    filetime::set_file_times(tdir.mkpath("file1"),
+                             filetime::FileTime::from_seconds_since_1970(1000, 0),
+                             filetime::FileTime::from_seconds_since_1970(1000, 0))
+        .expect("failed to set file times");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::WRITE, None),
+    ]);
+}
+
+#[test]
+#[cfg(unix)]
+fn poll_content_hash_without_write() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+    fs::write(tdir.mkpath("file1"), b"aaaa").expect("failed to write to file");
+
+    let (tx, rx) = mpsc::channel();
+    let builder = WatcherBuilder::new()
+        .poll_interval(Duration::from_millis(50))
+        .content_hash(ContentHash::default());
+    let mut watcher: PollWatcher = builder.build_raw(tx).expect("failed to create poll watcher");
+    watcher.watch_with(&tdir.mkpath("file1"), WatchOptions {
+        ops: op::CHMOD,
+        ..WatchOptions::default()
+    }).expect("failed to watch file");
+
+    sleep(100);
+
+    // the contents aren't read when writes aren't reported, so the new ones go unnoticed and
+    // only the status change is left
+    write_preserving_mtime(&tdir.mkpath("file1"), b"bbbb");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::CHMOD, None),
+    ]);
+}
+
+#[test]
+fn poll_configure_interval() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: PollWatcher = Watcher::new_raw(tx).expect("failed to create poll watcher"); // polls every 30 seconds
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
+
+    assert!(watcher.configure(Config::PollInterval(Duration::from_millis(50))).expect("failed to configure poll watcher"));
+    assert!(!watcher.configure(Config::RenameWindow(Duration::from_millis(50))).expect("failed to configure poll watcher"));
+    assert!(!watcher.configure(Config::DebounceDelay(Duration::from_millis(50))).expect("failed to configure poll watcher"));
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+
+    tdir.write("file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::WRITE, None)
+    ]);
+}
+
+#[test]
+fn builder_poll_interval() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: PollWatcher = WatcherBuilder::new()
+        .poll_interval(Duration::from_millis(50))
+        .thread_name("poll watcher test")
+        .build_raw(tx)
+        .expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+
+    tdir.write("file1");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::WRITE, None)
+    ]);
+}
+
+#[test]
+fn builder_debounced_requires_delay() {
+    let (tx, _) = mpsc::channel();
+    let w: Result<RecommendedWatcher> = WatcherBuilder::new().build(tx);
+    match w {
+        Err(Error { kind: ErrorKind::InvalidConfig(_), .. }) => (),
+        Err(e) => panic!("{:?}", e),
+        Ok(_) => panic!("watcher was created without a debounce delay"),
+    }
+
+    let (tx, _) = mpsc::channel();
+    let w: Result<RecommendedWatcher> = WatcherBuilder::new().debounce_delay(Duration::from_secs(1)).build(tx);
+    assert!(w.is_ok());
+}
+
+#[test]
+fn builder_backend() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = WatcherBuilder::new()
+        .backend(Backend::Poll)
+        .poll_interval(Duration::from_millis(50))
+        .build_boxed_raw(tx)
+        .expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+
+    tdir.write("file1");
+
+    // the event driven backends would report CLOSE_WRITE too
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::WRITE, None)
+    ]);
+}
+
+#[test]
+fn builder_debounced_thread_name() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: PollWatcher = WatcherBuilder::new()
+        .debounce_delay(Duration::from_millis(50))
+        .thread_name("builder test")
+        .build(move |event| {
+            let _ = tx.send((event, thread::current().name().map(String::from)));
+        })
+        .expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.create("file1");
+
+    // creations are delivered by the debouncer's timer thread once the delay passed
+    loop {
+        match rx.recv_timeout(Duration::from_secs(2)).expect("no create event received") {
+            (DebouncedEvent::Create(path), name) => {
+                assert_eq!(path, tdir.mkpath("file1"));
+                assert_eq!(name, Some("builder test debouncer".to_owned()));
+                break;
+            }
+            _ => (),
+        }
+    }
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_configure() {
+    let (tx, _) = mpsc::channel();
+    let mut watcher: INotifyWatcher = WatcherBuilder::new()
+        .rename_window(Duration::from_millis(50))
+        .thread_name("inotify test")
+        .build_raw(tx)
+        .expect("failed to create inotify watcher");
+
+    assert!(watcher.configure(Config::RenameWindow(Duration::from_millis(20))).expect("failed to configure inotify watcher"));
+    assert!(!watcher.configure(Config::PollInterval(Duration::from_millis(20))).expect("failed to configure inotify watcher"));
+    assert!(!watcher.configure(Config::DebounceDelay(Duration::from_millis(20))).expect("failed to configure inotify watcher"));
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_rename_window() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/file1",
+        "dir2",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = WatcherBuilder::new()
+        .rename_window(Duration::from_millis(300))
+        .build_raw(tx)
+        .expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.rename("dir1/file1", "dir2/file1");
+
+    assert_eq!(recv_events(&rx), vec![]);
+
+    sleep(300);
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("dir1/file1"), op::REMOVE, None),
+    ]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_access_ops() {
+    use std::io::Read;
+
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+    tdir.write("file1");
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = WatcherBuilder::new()
+        .access_ops(op::ACCESS | op::OPEN | op::CLOSE_NOWRITE)
+        .build_raw(tx)
+        .expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::NonRecursive).expect("failed to watch file");
+
+    let (tx, rx_default) = mpsc::channel();
+    let mut watcher_default: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher_default.watch(&tdir.mkpath("file1"), RecursiveMode::NonRecursive).expect("failed to watch file");
+
+    let mut content = String::new();
+    std::fs::File::open(tdir.mkpath("file1")).expect("failed to open file")
+        .read_to_string(&mut content).expect("failed to read file");
+
+    assert_eq!(recv_events(&rx), vec![
+        (tdir.mkpath("file1"), op::OPEN, None),
+        (tdir.mkpath("file1"), op::ACCESS, None),
+        (tdir.mkpath("file1"), op::CLOSE_NOWRITE, None),
+    ]);
+    assert_eq!(recv_events(&rx_default), vec![]);
+}
+
+#[test]
+#[cfg(target_os="linux")]
+#[ignore] // requires the permission to mount a filesystem
+fn inotify_watch_unmount() {
+    use std::process::Command;
+
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1",
+    ]);
+    let status = Command::new("mount").args(&["-t", "tmpfs", "none"]).arg(tdir.mkpath("dir1"))
+        .status().expect("failed to run mount");
+    assert!(status.success());
+    tdir.create_all(vec![
+        "dir1/dir2",
+        "dir1/dir3",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("dir1/dir2"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    let status = Command::new("umount").arg(tdir.mkpath("dir1"))
+        .status().expect("failed to run umount");
+    assert!(status.success());
+
+    let mut actual = recv_events(&rx);
+    actual.sort_by(|a, b| a.0.cmp(&b.0));
+
+    assert_eq!(actual, vec![
+        (tdir.mkpath("dir1"), op::UNMOUNT, None),
+        (tdir.mkpath("dir1/dir2"), op::UNMOUNT, None),
+        (tdir.mkpath("dir1/dir2"), op::WATCH_DROPPED, None),
+    ]);
+
+    watcher.unwatch(&tdir.mkpath(".")).expect("failed to unwatch directory");
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn inotify_watch_dropped_moved_out() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+    let tdir_out = TempDir::new("temp_dir_out").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1/dir2/dir3",
+    ]);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: INotifyWatcher = Watcher::new_raw(tx).expect("failed to create inotify watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    watcher.watch(&tdir.mkpath("dir1/dir2"), RecursiveMode::NonRecursive).expect("failed to watch directory");
+
+    fs::rename(tdir.mkpath("dir1"), tdir_out.mkpath("dir1")).expect("failed to move directory");
+
+    let actual: Vec<_> = recv_events(&rx).into_iter().filter(|e| e.1 == op::WATCH_DROPPED).collect();
+    assert_eq!(actual, vec![
+        (tdir.mkpath("dir1/dir2"), op::WATCH_DROPPED, None),
+    ]);
+    assert_eq!(watcher.watched_paths().expect("failed to get watched paths"), vec![tdir.mkpath(".")]);
+}
+
+#[test]
+fn watch_nonexisting() {
+    let tdir1 = TempDir::new("temp_dir1").expect("failed to create temporary directory");
+    let tdir2 = TempDir::new("temp_dir2").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+    watcher.watch(&tdir1.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+    let result = watcher.watch(&tdir2.mkpath("non_existing"), RecursiveMode::Recursive);
+    assert!(result.is_err());
+
+    // make sure notify is still working
+
+    sleep_windows(100);
+
+    tdir1.create("file1");
+
+    if cfg!(target_os="macos") {
+        assert_eq!(inflate_events(recv_events(&rx)), vec![
+            (tdir1.mkpath("file1"), op::CREATE, None),
+        ]);
+    } else if cfg!(target_os="windows") {
+        assert_eq!(recv_events(&rx), vec![
+            (tdir1.mkpath("file1"), op::CREATE, None)
+        ]);
+    } else {
+        assert_eq!(recv_events(&rx), vec![
+            (tdir1.mkpath("file1"), op::CREATE, None),
+            (tdir1.mkpath("file1"), op::CLOSE_WRITE, None)
+        ]);
+    }
+}
+
+#[test]
+fn unwatch_file() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    sleep_macos(10);
+
+    let (tx, _) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
+
+    match watcher.unwatch(&tdir.mkpath("file1")) {
+        Ok(_) => (),
+        Err(e) => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn unwatch_directory() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1",
+    ]);
+
+    sleep_macos(10);
+
+    let (tx, _) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    match watcher.unwatch(&tdir.mkpath("dir1")) {
+        Ok(_) => (),
+        Err(e) => panic!("{:?}", e),
+    }
+}
+
+#[test]
+#[cfg(not(target_os="windows"))]
+fn unwatch_nonexisting() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let (tx, _) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+
+    match watcher.unwatch(&tdir.mkpath("file1")) {
+        Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
+        Err(e) => panic!("{:?}", e),
+        Ok(o) => panic!("{:?}", o),
+    }
+}
+
+#[test]
+#[cfg(not(target_os="windows"))]
+fn unwatch_nonexisting_error_has_path() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let (tx, _) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+
+    match watcher.unwatch(&tdir.mkpath("file1")) {
+        Err(e) => assert_eq!(e.paths, vec![tdir.mkpath("file1")]),
+        Ok(o) => panic!("{:?}", o),
+    }
+}
+
+#[test]
+#[cfg(target_os="linux")]
+fn watch_nonexisting_error_has_path() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    let (tx, _) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+
+    match watcher.watch(&tdir.mkpath("non_existing"), RecursiveMode::Recursive) {
+        Err(Error { kind: ErrorKind::Io(_), paths }) => {
+            assert_eq!(paths, vec![tdir.mkpath("non_existing")])
+        }
+        Err(e) => panic!("{:?}", e),
+        Ok(o) => panic!("{:?}", o),
+    }
+}
+
+#[test]
+fn self_delete_file() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    sleep_macos(10);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
+
+    sleep_windows(100);
+
+    tdir.remove("file1");
+
+    let actual = if cfg!(target_os="macos") {
+        inflate_events(recv_events(&rx))
+    } else {
+        recv_events(&rx)
+    };
+
+    if cfg!(target_os="windows") {
+        assert_eq!(actual, vec![
+            (tdir.mkpath("file1"), op::REMOVE, None),
+        ]);
+    } else if cfg!(target_os="macos") {
+        assert_eq!(actual, vec![
+            (tdir.mkpath("file1"), op::CREATE | op::REMOVE, None),
+        ]);
+    } else {
+        assert_eq!(actual, vec![
+            (tdir.mkpath("file1"), op::CHMOD, None),
+            (tdir.mkpath("file1"), op::REMOVE, None),
+            (tdir.mkpath("file1"), op::WATCH_DROPPED, None),
+        ]);
+    }
+
+    if cfg!(not(any(target_os="windows", target_os="macos"))) {
+        tdir.create("file1");
+
+        assert_eq!(recv_events(&rx), vec![]);
+
+        match watcher.unwatch(&tdir.mkpath("file1")) {
+            Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
+            Err(e) => panic!("{:?}", e),
+            Ok(o) => panic!("{:?}", o),
+        }
+    }
+}
+
+#[test]
+#[cfg(not(target_os="windows"))]
+fn self_delete_directory() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "dir1",
+    ]);
+
+    sleep_macos(10);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = Watcher::new_raw(tx).expect("failed to create recommended watcher");
+    watcher.watch(&tdir.mkpath("dir1"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep_windows(100);
+
+    tdir.remove("dir1");
+
+    let actual = if cfg!(target_os="macos") {
+        inflate_events(recv_events(&rx))
+    } else {
         recv_events(&rx)
     };
 
@@ -955,6 +1698,7 @@ fn self_delete_directory() {
     } else {
         assert_eq!(actual, vec![
             (tdir.mkpath("dir1"), op::REMOVE, None),
+            (tdir.mkpath("dir1"), op::WATCH_DROPPED, None),
         ]);
     }
 
@@ -976,7 +1720,7 @@ fn self_delete_directory() {
 
     if cfg!(not(any(target_os="windows", target_os="macos"))) {
         match watcher.unwatch(&tdir.mkpath("dir1")) {
-            Err(Error::WatchNotFound) => (),
+            Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
             Err(e) => panic!("{:?}", e),
             Ok(o) => panic!("{:?}", o),
         }
@@ -1058,7 +1802,7 @@ fn self_rename_file() {
 
     let result = watcher.unwatch(&tdir.mkpath("file1"));
     match result {
-        Err(Error::WatchNotFound) => (),
+        Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
         Err(e) => panic!("{:?}", e),
         Ok(o) => panic!("{:?}", o),
     }
@@ -1149,7 +1893,7 @@ fn self_rename_directory() {
         }
     } else {
         match result {
-            Err(Error::WatchNotFound) => (),
+            Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
             Err(e) => panic!("{:?}", e),
             Ok(o) => panic!("{:?}", o),
         }
@@ -1219,7 +1963,7 @@ fn parent_rename_file() {
         }
     } else {
         match result {
-            Err(Error::WatchNotFound) => (),
+            Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
             Err(e) => panic!("{:?}", e),
             Ok(o) => panic!("{:?}", o),
         }
@@ -1293,8 +2037,158 @@ fn parent_rename_directory() {
 
     let result = watcher.unwatch(&tdir.mkpath("dir1/watch_dir"));
     match result {
-        Err(Error::WatchNotFound) => (),
+        Err(Error { kind: ErrorKind::WatchNotFound, .. }) => (),
         Err(e) => panic!("{:?}", e),
         Ok(o) => panic!("{:?}", o),
     }
 }
+
+// Collect events from the stream until `timeout` milliseconds have passed. The executor parks
+// between events, so this also checks that the stream wakes its task.
+#[cfg(feature = "stream")]
+fn recv_stream_events<T>(events: &mut stream::EventStream<T>, timeout: u64) -> Vec<T> {
+    let (timeout_tx, mut timeout_rx) = oneshot::channel();
+    thread::spawn(move || {
+        sleep(timeout);
+        let _ = timeout_tx.send(());
+    });
+
+    let mut evs = Vec::new();
+    while let Either::Left((Some(event), _)) = block_on(future::select(events.next(), &mut timeout_rx)) {
+        evs.push(event);
+    }
+    evs
+}
+
+#[cfg(feature = "stream")]
+fn raw_stream_events(events: &mut stream::EventStream<RawEvent>, timeout: u64) -> Vec<(std::path::PathBuf, Op, Option<u32>)> {
+    recv_stream_events(events, timeout).into_iter().filter_map(|event| match event {
+        RawEvent { path: Some(path), op: Ok(op), cookie, .. } => Some((path, op, cookie)),
+        RawEvent { op: Err(e), .. } => panic!("unexpected event err: {:?}", e),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+#[cfg(feature = "stream")]
+fn stream_watch_recursive_create_directory() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let (mut watcher, mut events) = stream::raw_watcher::<RecommendedWatcher>().expect("failed to create recommended watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep_windows(100);
+
+    tdir.create("dir1");
+    sleep(10);
+    tdir.create("dir1/file1");
+
+    let actual = if cfg!(target_os="macos") {
+        inflate_events(raw_stream_events(&mut events, 100))
+    } else {
+        raw_stream_events(&mut events, 100)
+    };
+
+    if cfg!(target_os="linux") {
+        assert_eq!(actual, vec![
+            (tdir.mkpath("dir1"), op::CREATE, None),
+            (tdir.mkpath("dir1/file1"), op::CREATE, None),
+            (tdir.mkpath("dir1/file1"), op::CLOSE_WRITE, None)
+        ]);
+    } else {
+        assert_eq!(actual, vec![
+            (tdir.mkpath("dir1"), op::CREATE, None),
+            (tdir.mkpath("dir1/file1"), op::CREATE, None)
+        ]);
+    }
+}
+
+#[test]
+#[cfg(feature = "stream")]
+fn stream_ends_when_watcher_is_dropped() {
+    let (watcher, mut events) = stream::raw_watcher::<RecommendedWatcher>().expect("failed to create recommended watcher");
+
+    drop(watcher);
+
+    assert!(block_on(events.next()).is_none());
+}
+
+#[test]
+#[cfg(feature = "stream")]
+fn debounced_stream_ends_when_watcher_is_dropped() {
+    let (watcher, mut events) = stream::watcher::<RecommendedWatcher>(Duration::from_millis(100)).expect("failed to create recommended watcher");
+
+    drop(watcher);
+
+    assert!(block_on(events.next()).is_none());
+
+    let (watcher, mut events) = stream::watcher::<PollWatcher>(Duration::from_millis(100)).expect("failed to create poll watcher");
+
+    drop(watcher);
+
+    assert!(block_on(events.next()).is_none());
+}
+
+#[test]
+#[cfg(feature = "stream")]
+fn stream_poll_watch_file() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (handler, mut events) = stream::channel();
+    let mut watcher = PollWatcher::with_delay_ms(handler, 50).expect("failed to create poll watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+
+    tdir.write("file1");
+    tdir.create("file2");
+
+    assert_eq!(raw_stream_events(&mut events, 100), vec![
+        (tdir.mkpath("file1"), op::WRITE, None)
+    ]);
+}
+
+#[test]
+#[cfg(feature = "stream")]
+fn stream_debounced_create_file() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    sleep_macos(10);
+
+    let (mut watcher, mut events) = stream::watcher::<RecommendedWatcher>(Duration::from_millis(500)).expect("failed to create debounced watcher");
+    watcher.watch(&tdir.mkpath("."), RecursiveMode::Recursive).expect("failed to watch directory");
+
+    tdir.create("file1");
+
+    assert_eq!(recv_stream_events(&mut events, 1500), vec![
+        DebouncedEvent::Create(tdir.mkpath("file1")),
+    ]);
+}
+
+#[test]
+#[cfg(feature = "stream")]
+fn stream_poll_debounced_write_file() {
+    let tdir = TempDir::new("temp_dir").expect("failed to create temporary directory");
+
+    tdir.create_all(vec![
+        "file1",
+    ]);
+
+    let (mut watcher, mut events) = stream::watcher::<PollWatcher>(Duration::from_millis(500)).expect("failed to create debounced poll watcher");
+    watcher.watch(&tdir.mkpath("file1"), RecursiveMode::Recursive).expect("failed to watch file");
+
+    sleep(1100); // PollWatcher has only a resolution of 1 second
+
+    tdir.write("file1");
+
+    assert_eq!(recv_stream_events(&mut events, 2000), vec![
+        DebouncedEvent::NoticeWrite(tdir.mkpath("file1")),
+        DebouncedEvent::Write(tdir.mkpath("file1")),
+    ]);
+}